@@ -0,0 +1,121 @@
+//! Unsaved edits of tabs backed by a file, kept out of `session.json`.
+//!
+//! Before the session is written, the content of each such tab is moved to
+//! `buffers/<sha256>.txt` next to it and the tab only keeps the hash. Naming
+//! buffers by their content means unchanged edits aren't rewritten on every
+//! save and two tabs with the same edits share a file.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::commands::files::FileVersion;
+use crate::commands::migration::{DatabaseV4, SessionTab};
+use crate::utils;
+
+fn buffer_path(dir: &Path, hash: &str) -> PathBuf {
+    dir.join(format!("{}.txt", hash))
+}
+
+/// Whether `tab`'s content is stored out of line: it has a file and unsaved
+/// edits. Untitled tabs keep their content inline.
+fn stored_out_of_line(tab: &SessionTab) -> bool {
+    tab.path.is_some() && tab.is_dirty
+}
+
+/// Write the content of every dirty file-backed tab in `db` to `dir` and
+/// replace it with the buffer's hash. Returns `db` ready to be serialized.
+pub fn store(dir: &Path, mut db: DatabaseV4) -> std::io::Result<DatabaseV4> {
+    for session in db.sessions_mut() {
+        for tab in session.tabs.iter_mut().filter(|t| stored_out_of_line(t)) {
+            let Some(content) = tab.content.take() else {
+                continue;
+            };
+
+            let hash = utils::content_hash(content.as_bytes());
+            let path = buffer_path(dir, &hash);
+            if !path.exists() {
+                std::fs::create_dir_all(dir)?;
+                utils::atomic_write(&path, content)?;
+            }
+            tab.buffer = Some(hash);
+        }
+    }
+
+    Ok(db)
+}
+
+/// Read the buffers referenced by `db` back into their tabs. A tab whose
+/// buffer is missing or doesn't match its hash loses its unsaved edits and
+/// is reloaded from disk; the paths of those tabs are returned.
+pub fn load(dir: &Path, db: &mut DatabaseV4) -> Vec<String> {
+    let mut lost = Vec::new();
+
+    for session in db.sessions_mut() {
+        for tab in &mut session.tabs {
+            let Some(hash) = tab.buffer.take() else {
+                continue;
+            };
+
+            match std::fs::read(buffer_path(dir, &hash)) {
+                Ok(bytes) if utils::content_hash(&bytes) == hash => {
+                    tab.content = Some(String::from_utf8_lossy(&bytes).into_owned());
+                }
+                _ => {
+                    tab.is_dirty = false;
+                    tab.base_version = None;
+                    lost.extend(tab.path.clone());
+                }
+            }
+        }
+    }
+
+    lost
+}
+
+/// Delete the buffers in `dir` that no tab of the stored `db` refers to.
+pub fn prune(dir: &Path, db: &DatabaseV4) {
+    let referenced: HashSet<String> = db
+        .tabs()
+        .filter_map(|t| t.buffer.as_ref())
+        .map(|hash| format!("{}.txt", hash))
+        .collect();
+
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !referenced.contains(&name)
+            && let Err(e) = std::fs::remove_file(entry.path())
+        {
+            eprintln!("Warning: could not remove unused buffer {}: {}", name, e);
+        }
+    }
+}
+
+/// Paths of the dirty tabs whose file changed on disk, or disappeared, since
+/// the edits were made.
+pub async fn changed_on_disk(db: &DatabaseV4) -> Vec<String> {
+    let mut changed = Vec::new();
+
+    for tab in db.tabs().filter(|t| stored_out_of_line(t)) {
+        let (Some(path), Some(base)) = (&tab.path, &tab.base_version) else {
+            continue;
+        };
+        if changed.contains(path) {
+            continue;
+        }
+
+        let current = match (tokio::fs::metadata(path).await, tokio::fs::read(path).await) {
+            (Ok(metadata), Ok(bytes)) => Some(FileVersion::new(&metadata, &bytes)),
+            _ => None,
+        };
+
+        if !current.is_some_and(|current| current.same_content(base)) {
+            changed.push(path.clone());
+        }
+    }
+
+    changed
+}
@@ -0,0 +1,393 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::migration::SessionTab;
+use crate::utils::MAX_FILE_SIZE;
+use crate::wait::{WAIT_PORT_FLAG, WAIT_TOKEN_FLAG, WaitChannel, Waiters};
+use crate::{encoding, handoff};
+
+/// Something the command line asked the editor to open.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenRequest {
+    #[serde(flatten)]
+    pub target: OpenTarget,
+    pub read_only: bool,
+    pub new_window: bool,
+    /// Set when a process is blocked in `--wait` until this request's tab is
+    /// saved or closed; pass it to `finish_wait`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wait_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum OpenTarget {
+    /// A file, optionally at a 1-based line and column.
+    #[serde(rename_all = "camelCase")]
+    File {
+        path: String,
+        /// The file doesn't exist yet and should be offered as a new file.
+        #[serde(default)]
+        new_file: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        line: Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        column: Option<u32>,
+    },
+    /// A folder to pick files from.
+    Directory { path: String },
+    /// Two files to compare.
+    Diff { left: String, right: String },
+    /// Whatever is piped into the process (`-`). The parser leaves both
+    /// fields empty; [`attach_stdin`] fills them in.
+    Stdin { filename: String, content: String },
+    /// A tab moved over from another window, with its unsaved content. Never
+    /// comes from the command line.
+    Tab { tab: SessionTab },
+}
+
+/// A parsed command line.
+#[derive(Debug, Default)]
+pub struct Invocation {
+    pub requests: Vec<OpenRequest>,
+    /// `--new-window`: open in a new window even if one is already open.
+    pub new_window: bool,
+    /// `--wait`: block until the opened tabs are saved or closed.
+    pub wait: bool,
+    /// Set on the copy started by a `--wait` process, see
+    /// [`run_waiter`](crate::wait::run_waiter): the port to report back on
+    /// and the name of the [`handoff`] file holding the token.
+    pub wait_channel: Option<(u16, String)>,
+    /// Name of the [`handoff`] file holding our piped stdin, see
+    /// [`spool_stdin`].
+    pub stdin_spool: Option<String>,
+}
+
+impl Invocation {
+    /// Register the `--wait` channel, if any, and tag every request with its
+    /// wait id.
+    pub fn register_wait(&mut self, waiters: &Waiters) {
+        let Some((port, token_file)) = self.wait_channel.take() else {
+            return;
+        };
+
+        match WaitChannel::from_args(port, &token_file) {
+            Ok(channel) => {
+                let id = waiters.register(channel);
+                for request in &mut self.requests {
+                    request.wait_id = Some(id.clone());
+                }
+            }
+            Err(e) => eprintln!("Error reading the --wait token: {}", e),
+        }
+    }
+}
+
+const USAGE: &str = "usage: taking-notes [--new-window] [--read-only] [--wait] \
+[--goto FILE:LINE[:COL]] [--diff LEFT RIGHT] [FILE[:LINE[:COL]] | DIR | -]...";
+
+/// Parse the arguments after the executable name.
+///
+/// Paths are resolved against `cwd`, the working directory of the process
+/// that received them, which for a forwarded second instance is not ours.
+/// `--new-window` and `--read-only` apply to everything opened by the same
+/// command line. A trailing `:LINE[:COL]` is read as a position when the
+/// argument isn't itself an existing path; `--goto` always reads it. Unknown
+/// flags are ignored, since the OS may add its own.
+pub fn parse_args<I, S>(args: I, cwd: &Path) -> Result<Invocation, String>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let mut args = args.into_iter().map(Into::into);
+    let mut targets = Vec::new();
+    let mut read_only = false;
+    let mut new_window = false;
+    let mut wait = false;
+    let mut wait_port = None;
+    let mut wait_token_file = None;
+    let mut stdin_spool = None;
+    let mut options_done = false;
+
+    while let Some(arg) = args.next() {
+        if options_done || arg == "-" || !arg.starts_with('-') {
+            targets.push(if arg == "-" && !options_done {
+                OpenTarget::Stdin {
+                    filename: String::new(),
+                    content: String::new(),
+                }
+            } else {
+                path_target(&arg, false, cwd)
+            });
+            continue;
+        }
+
+        if let Some(port) = arg.strip_prefix(WAIT_PORT_FLAG) {
+            wait_port = port.parse::<u16>().ok();
+            continue;
+        }
+        if let Some(name) = arg.strip_prefix(WAIT_TOKEN_FLAG) {
+            wait_token_file = Some(name.to_string());
+            continue;
+        }
+        if let Some(name) = arg.strip_prefix(STDIN_SPOOL_FLAG) {
+            stdin_spool = Some(name.to_string());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => options_done = true,
+            "--new-window" => new_window = true,
+            "--read-only" => read_only = true,
+            "--wait" => wait = true,
+            "--goto" => {
+                let spec = args
+                    .next()
+                    .ok_or_else(|| format!("--goto needs FILE:LINE[:COL]\n{}", USAGE))?;
+                targets.push(path_target(&spec, true, cwd));
+            }
+            "--diff" => {
+                let (Some(left), Some(right)) = (args.next(), args.next()) else {
+                    return Err(format!("--diff needs two files\n{}", USAGE));
+                };
+                targets.push(OpenTarget::Diff {
+                    left: path_string(resolve_path(cwd, &left)),
+                    right: path_string(resolve_path(cwd, &right)),
+                });
+            }
+            other => eprintln!("Ignoring unknown argument {}", other),
+        }
+    }
+
+    Ok(Invocation {
+        requests: targets
+            .into_iter()
+            .map(|target| OpenRequest {
+                target,
+                read_only,
+                new_window,
+                wait_id: None,
+            })
+            .collect(),
+        new_window,
+        wait,
+        wait_channel: wait_port.zip(wait_token_file),
+        stdin_spool,
+    })
+}
+
+/// Turn a path argument into a file or directory target.
+fn path_target(arg: &str, force_position: bool, cwd: &Path) -> OpenTarget {
+    let (arg, line, column) = if force_position || !resolve_path(cwd, arg).exists() {
+        split_position(arg)
+    } else {
+        (arg, None, None)
+    };
+
+    let path = resolve_path(cwd, arg);
+
+    if path.is_dir() {
+        return OpenTarget::Directory {
+            path: path_string(path),
+        };
+    }
+
+    OpenTarget::File {
+        new_file: !path.exists(),
+        path: path_string(path),
+        line,
+        column,
+    }
+}
+
+fn path_string(path: PathBuf) -> String {
+    path.to_string_lossy().to_string()
+}
+
+/// Absolute form of `arg` as seen from `cwd`.
+///
+/// Existing paths are canonicalized. For a path that doesn't exist yet, its
+/// nearest existing ancestor is canonicalized and the rest appended.
+pub fn resolve_path(cwd: &Path, arg: &str) -> PathBuf {
+    let joined = cwd.join(arg);
+    let mut existing = joined.as_path();
+    let mut missing = Vec::new();
+
+    loop {
+        if let Ok(canonical) = std::fs::canonicalize(existing) {
+            let mut resolved = strip_verbatim(canonical);
+            resolved.extend(missing.iter().rev());
+            return resolved;
+        }
+
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                existing = parent;
+            }
+            _ => return joined,
+        }
+    }
+}
+
+/// `canonicalize` returns `\\?\C:\...` paths on Windows; keep the familiar
+/// form so they match paths coming from the file dialogs.
+fn strip_verbatim(path: PathBuf) -> PathBuf {
+    #[cfg(windows)]
+    if let Some(plain) = path.to_str().and_then(|s| s.strip_prefix(r"\\?\"))
+        && !plain.starts_with("UNC\\")
+    {
+        return PathBuf::from(plain);
+    }
+
+    path
+}
+
+/// Names the [`handoff`] file holding the piped stdin of the command line.
+const STDIN_SPOOL_FLAG: &str = "--stdin-spool=";
+
+/// Whether the command line `args` asks for stdin (`-`) that hasn't been
+/// spooled yet.
+pub fn reads_stdin(args: &[String]) -> bool {
+    let options = || args.iter().take_while(|a| *a != "--");
+    options().any(|a| a == "-") && !options().any(|a| a.starts_with(STDIN_SPOOL_FLAG))
+}
+
+/// Read our stdin into a new [`handoff`] file and return the argument that
+/// names it. Input over [`MAX_FILE_SIZE`] is rejected, same as opening a
+/// file that large.
+///
+/// A second instance can only forward its arguments, so the process has to
+/// continue with that argument added, see [`relaunch`].
+pub fn spool_stdin() -> Result<String, String> {
+    let mut bytes = Vec::new();
+    std::io::stdin()
+        .take(MAX_FILE_SIZE + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Error reading stdin: {}", e))?;
+
+    if bytes.len() as u64 > MAX_FILE_SIZE {
+        return Err(format!(
+            "stdin is too large. The maximum supported size is {:.0} MB.",
+            MAX_FILE_SIZE as f64 / (1024.0 * 1024.0),
+        ));
+    }
+
+    let name = handoff::write(&bytes).map_err(|e| format!("Error spooling stdin: {}", e))?;
+    Ok(format!("{}{}", STDIN_SPOOL_FLAG, name))
+}
+
+/// Start a copy of this process with `args` instead and exit right away, so
+/// a shell pipeline feeding the editor returns once the input is handed over.
+///
+/// The copy runs in its own process group and doesn't inherit stdout, so it
+/// outlives the terminal job and doesn't hold a downstream pipe open.
+pub fn relaunch(args: &[String]) -> ! {
+    let spawned = std::env::current_exe().and_then(|exe| {
+        let mut command = Command::new(exe);
+        command
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null());
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+        command.spawn()
+    });
+
+    match spawned {
+        Ok(_) => std::process::exit(0),
+        Err(e) => {
+            eprintln!("Error starting the editor: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Fill the `-` requests of `invocation` with its spooled stdin, consuming
+/// the spool file. Requests whose input can't be read are dropped.
+pub fn attach_stdin(invocation: &mut Invocation) {
+    let requests = &mut invocation.requests;
+    if !requests
+        .iter()
+        .any(|r| matches!(r.target, OpenTarget::Stdin { .. }))
+    {
+        return;
+    }
+
+    let text = invocation
+        .stdin_spool
+        .as_deref()
+        .ok_or_else(|| "stdin was not spooled".to_string())
+        .and_then(|name| {
+            handoff::take(name).map_err(|e| format!("Error reading spooled stdin: {}", e))
+        })
+        .and_then(|bytes| {
+            encoding::decode(&bytes)
+                .map(|d| d.content)
+                .map_err(|e| format!("Error decoding stdin: {}", e))
+        });
+
+    match text {
+        Ok(content) => {
+            let filename = stdin_filename(&content);
+            for request in requests.iter_mut() {
+                if let OpenTarget::Stdin { .. } = request.target {
+                    request.target = OpenTarget::Stdin {
+                        filename: filename.clone(),
+                        content: content.clone(),
+                    };
+                }
+            }
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            requests.retain(|r| !matches!(r.target, OpenTarget::Stdin { .. }));
+        }
+    }
+}
+
+/// `stdin-<timestamp>.<ext>`, with the extension guessed from the content.
+fn stdin_filename(content: &str) -> String {
+    let trimmed = content.trim_start();
+    let ext = if trimmed.starts_with("diff --git") || trimmed.starts_with("--- ") {
+        "diff"
+    } else if (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(content).is_ok()
+    {
+        "json"
+    } else {
+        "txt"
+    };
+
+    format!(
+        "stdin-{}.{}",
+        chrono::Local::now().format("%Y%m%d-%H%M%S"),
+        ext
+    )
+}
+
+/// Split `path:line:column` or `path:line` from the right. Parts that aren't
+/// positive numbers stay in the path, so `C:\notes.txt` is left alone.
+fn split_position(arg: &str) -> (&str, Option<u32>, Option<u32>) {
+    let number = |s: &str| s.parse::<u32>().ok().filter(|n| *n > 0);
+
+    let Some((rest, last)) = arg.rsplit_once(':') else {
+        return (arg, None, None);
+    };
+    let Some(last) = number(last) else {
+        return (arg, None, None);
+    };
+
+    match rest.rsplit_once(':') {
+        Some((path, line)) if !path.is_empty() => match number(line) {
+            Some(line) => (path, Some(line), Some(last)),
+            None => (rest, Some(last), None),
+        },
+        _ if rest.is_empty() => (arg, None, None),
+        _ => (rest, Some(last), None),
+    }
+}
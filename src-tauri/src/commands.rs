@@ -1,2 +1,10 @@
+pub mod closed_tabs;
 pub mod files;
+pub mod history;
 pub mod migration;
+pub mod recent_files;
+pub mod recovery;
+pub mod settings;
+pub mod view_state;
+pub mod windows;
+pub mod workspaces;
@@ -0,0 +1,34 @@
+use tauri::{AppHandle, command};
+
+use crate::commands::migration::{self, ClosedTab, SessionTab};
+
+/// Remember a tab the user just closed so it can be reopened, even after a
+/// restart.
+#[command]
+pub async fn record_closed_tab(app: AppHandle, tab: SessionTab) -> Result<(), String> {
+    migration::update_session(&app, |db| {
+        db.push_closed_tab(tab);
+        Ok(())
+    })
+    .await
+}
+
+/// The closed tabs that can be reopened, most recently closed first.
+#[command]
+pub async fn list_closed_tabs(app: AppHandle) -> Result<Vec<ClosedTab>, String> {
+    migration::inspect_session(&app, |db| db.closed_tabs.clone()).await
+}
+
+/// Take closed tab `id` off the stack to reopen it, or the most recently
+/// closed one when `id` is `None`. Returns `None` when there is nothing to
+/// reopen.
+#[command]
+pub async fn reopen_closed_tab(
+    app: AppHandle,
+    id: Option<String>,
+) -> Result<Option<SessionTab>, String> {
+    migration::update_session(&app, |db| {
+        Ok(db.take_closed_tab(id.as_deref()).map(|closed| closed.tab))
+    })
+    .await
+}
@@ -1,15 +1,103 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
+use similar::TextDiff;
 use tauri::Result;
 
 use crate::CliFilePaths;
-use crate::utils::{MAX_FILE_SIZE, atomic_write_async};
+use crate::cli::OpenRequest;
+use crate::commands::history;
+use crate::encoding::{self, DecodedText, TextEncoding};
+use crate::line_ending::{LineEnding, LineEndingPolicy, detect_line_ending, normalize};
+use crate::utils::{MAX_FILE_SIZE, atomic_write_async, content_hash, modified_millis};
+use crate::wait::Waiters;
+use crate::watcher::FileWatcher;
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Entry {
     path: PathBuf,
     content: String,
+    /// Encoding to write with, e.g. "UTF-16LE". When omitted the encoding of
+    /// the file currently on disk is kept (UTF-8 for new files).
+    #[serde(default)]
+    encoding: Option<String>,
+    /// Whether to write a byte-order mark. When omitted the BOM state of the
+    /// file on disk is kept.
+    #[serde(default)]
+    with_bom: Option<bool>,
+    /// Line endings to write. Defaults to preserving the on-disk style.
+    #[serde(default)]
+    line_ending: LineEndingPolicy,
+    /// Version returned when the file was opened (or last saved). When set,
+    /// the save is refused if the file on disk no longer matches it.
+    #[serde(default)]
+    expected_version: Option<FileVersion>,
+    /// Overwrite even if `expected_version` doesn't match.
+    #[serde(default)]
+    force: bool,
+}
+
+/// Snapshot of a file's on-disk state, used to notice changes made by other
+/// programs between opening and saving.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileVersion {
+    /// Last modification time in unix milliseconds.
+    pub modified: u64,
+    /// Size in bytes.
+    pub size: u64,
+    /// Hex-encoded SHA-256 of the raw bytes.
+    pub hash: String,
+}
+
+impl FileVersion {
+    pub fn new(metadata: &std::fs::Metadata, bytes: &[u8]) -> Self {
+        FileVersion {
+            modified: modified_millis(metadata),
+            size: bytes.len() as u64,
+            hash: content_hash(bytes),
+        }
+    }
+
+    /// Two versions match when their content does; a bare `touch` that only
+    /// bumps the mtime is not a conflict.
+    pub fn same_content(&self, other: &FileVersion) -> bool {
+        self.size == other.size && self.hash == other.hash
+    }
+}
+
+/// Error returned by `save_file`. Serialized as `{ "kind": ..., ... }` so the
+/// frontend can tell a conflict apart from an ordinary failure.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum SaveError {
+    /// The file changed on disk after `expected` was taken. `actual` is
+    /// `None` when the file has been deleted.
+    Conflict {
+        expected: FileVersion,
+        actual: Option<FileVersion>,
+    },
+    Io {
+        message: String,
+    },
+}
+
+impl From<std::io::Error> for SaveError {
+    fn from(e: std::io::Error) -> Self {
+        SaveError::Io {
+            message: e.to_string(),
+        }
+    }
+}
+
+/// Returned by `save_file` on success.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedFile {
+    pub message: String,
+    /// Version of the file as just written; send it back on the next save.
+    pub version: FileVersion,
 }
 
 /// Metadata about a file detected at read time.
@@ -18,13 +106,18 @@ pub struct Entry {
 pub struct FileInfo {
     /// Detected line-ending style: "CRLF", "LF", "Mixed", or "N/A".
     pub line_ending: String,
-    /// Encoding used to read the file (always "UTF-8" for now).
+    /// Detected encoding used to decode the file, e.g. "UTF-8", "UTF-16LE",
+    /// "windows-1252".
     pub encoding: String,
+    /// Whether the file starts with a byte-order mark.
+    pub has_bom: bool,
     /// On-disk file size in bytes at the moment it was read.
     pub file_size: u64,
     /// File extension without the leading dot, e.g. "txt", "md".
     /// Empty string when there is no extension.
     pub extension: String,
+    /// Version token to pass back to `save_file` for conflict detection.
+    pub version: FileVersion,
 }
 
 /// Bundle returned by `open_file` so the frontend gets content + metadata
@@ -36,39 +129,6 @@ pub struct OpenedFile {
     pub file_info: FileInfo,
 }
 
-/// Efficiently detect the dominant line-ending style by scanning raw bytes.
-///
-/// Returns early with `"Mixed"` as soon as both styles are found.
-fn detect_line_ending(content: &str) -> &'static str {
-    let bytes = content.as_bytes();
-    let mut has_crlf = false;
-    let mut has_lf_only = false;
-
-    let mut i = 0;
-    while i < bytes.len() {
-        if bytes[i] == b'\r' && i + 1 < bytes.len() && bytes[i + 1] == b'\n' {
-            has_crlf = true;
-            i += 2;
-        } else if bytes[i] == b'\n' {
-            has_lf_only = true;
-            i += 1;
-        } else {
-            i += 1;
-        }
-
-        if has_crlf && has_lf_only {
-            return "Mixed";
-        }
-    }
-
-    match (has_crlf, has_lf_only) {
-        (true, false) => "CRLF",
-        (false, true) => "LF",
-        (true, true) => "Mixed",
-        (false, false) => "N/A",
-    }
-}
-
 /// Extract the file extension (without the dot) from a path.
 fn extract_extension(path: &PathBuf) -> String {
     path.extension()
@@ -77,23 +137,196 @@ fn extract_extension(path: &PathBuf) -> String {
         .to_string()
 }
 
-/// Drain and return file paths that were passed via CLI arguments on cold start.
-/// Called once by the frontend during initialization.
+/// Drain and return the open requests queued for the calling window: the CLI
+/// arguments on cold start, or what a new window was opened for. Called once
+/// by the frontend during initialization.
+#[tauri::command]
+pub fn take_cli_file_paths(
+    window: tauri::WebviewWindow,
+    state: tauri::State<'_, CliFilePaths>,
+) -> Vec<OpenRequest> {
+    state
+        .0
+        .lock()
+        .unwrap()
+        .remove(window.label())
+        .unwrap_or_default()
+}
+
+/// Release the `--wait` process waiting on `wait_id`. The frontend calls this
+/// once every tab opened by that command line has been saved or closed.
 #[tauri::command]
-pub fn take_cli_file_paths(state: tauri::State<'_, CliFilePaths>) -> Vec<String> {
-    state.0.lock().unwrap().drain(..).collect()
+pub fn finish_wait(waiters: tauri::State<'_, Waiters>, wait_id: String) {
+    waiters.finish(&wait_id);
 }
 
+/// Replace the set of files watched for external changes in the calling
+/// window with `paths`, normally the paths of every tab open in it.
 #[tauri::command]
-pub async fn save_file(entry: Entry) -> Result<Option<String>> {
+pub fn watch_open_files(
+    window: tauri::WebviewWindow,
+    watcher: tauri::State<'_, FileWatcher>,
+    paths: Vec<String>,
+) {
+    watcher.watch_window_paths(window.label(), paths);
+}
+
+/// Result of an in-place line-ending conversion.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineEndingConversion {
+    /// Line ending now used by the file: "CRLF" or "LF".
+    pub line_ending: String,
+    /// Number of line terminators that were rewritten.
+    pub changed_lines: usize,
+}
+
+/// Refuse files larger than [`MAX_FILE_SIZE`], which the editor won't load
+/// whole into memory.
+fn check_size(size: u64) -> std::io::Result<()> {
+    if size <= MAX_FILE_SIZE {
+        return Ok(());
+    }
+
+    Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!(
+            "File is too large ({:.1} MB). The maximum supported size is {:.0} MB.",
+            size as f64 / (1024.0 * 1024.0),
+            MAX_FILE_SIZE as f64 / (1024.0 * 1024.0),
+        ),
+    ))
+}
+
+/// What `save_file` knows about the file it is about to replace.
+struct OnDisk {
+    bytes: Vec<u8>,
+    version: FileVersion,
+    /// `None` when the current contents can't be decoded as text.
+    decoded: Option<DecodedText>,
+}
+
+/// Read the file currently at `path`, if there is one we can read. A file
+/// over [`MAX_FILE_SIZE`] is an error rather than read whole.
+///
+/// When its mtime and size still match `expected`, the file is taken to be
+/// unchanged and isn't hashed again.
+async fn read_existing(
+    path: &Path,
+    expected: Option<&FileVersion>,
+) -> std::io::Result<Option<OnDisk>> {
+    let Ok(metadata) = tokio::fs::metadata(path).await else {
+        return Ok(None);
+    };
+    check_size(metadata.len())?;
+
+    let Ok(bytes) = tokio::fs::read(path).await else {
+        return Ok(None);
+    };
+
+    let modified = modified_millis(&metadata);
+    let version = match expected {
+        Some(expected) if expected.modified == modified && expected.size == bytes.len() as u64 => {
+            expected.clone()
+        }
+        _ => FileVersion::new(&metadata, &bytes),
+    };
+
+    Ok(Some(OnDisk {
+        version,
+        decoded: encoding::decode(&bytes).ok(),
+        bytes,
+    }))
+}
+
+/// Work out which encoding and BOM setting a save should use.
+///
+/// Explicit choices from the frontend win; anything left unspecified is taken
+/// from the file currently on disk so a plain save round-trips the original
+/// encoding.
+fn resolve_target_encoding(
+    entry: &Entry,
+    on_disk: Option<&DecodedText>,
+) -> std::io::Result<(TextEncoding, bool)> {
+    let requested = match &entry.encoding {
+        Some(label) => Some(TextEncoding::from_label(label).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Unsupported encoding: {}", label),
+            )
+        })?),
+        None => None,
+    };
+
+    let target = requested
+        .or(on_disk.map(|d| d.encoding))
+        .unwrap_or(TextEncoding::Utf8);
+
+    let with_bom = entry.with_bom.unwrap_or_else(|| match on_disk {
+        Some(d) if d.encoding == target => d.has_bom,
+        _ => target.default_bom(),
+    });
+
+    Ok((target, with_bom))
+}
+
+/// Write a tab's content to disk.
+///
+/// The contents being replaced are kept as a snapshot in the local version
+/// history (see [`snapshots`](crate::snapshots)).
+#[tauri::command]
+pub async fn save_file(
+    app: tauri::AppHandle,
+    entry: Entry,
+) -> std::result::Result<SavedFile, SaveError> {
+    let on_disk = read_existing(&entry.path, entry.expected_version.as_ref()).await?;
+
+    if let Some(expected) = &entry.expected_version {
+        let actual = on_disk.as_ref().map(|d| &d.version);
+        let unchanged = actual.is_some_and(|actual| actual.same_content(expected));
+
+        if !unchanged && !entry.force {
+            return Err(SaveError::Conflict {
+                expected: expected.clone(),
+                actual: actual.cloned(),
+            });
+        }
+    }
+
+    let decoded = on_disk.as_ref().and_then(|d| d.decoded.as_ref());
+    let (target, with_bom) = resolve_target_encoding(&entry, decoded)?;
+
+    let line_ending = entry
+        .line_ending
+        .resolve(decoded.map(|d| d.content.as_str()));
+    let content = match line_ending {
+        Some(le) => normalize(&entry.content, le).0,
+        None => entry.content,
+    };
+
     let path = entry.path;
-    let content = entry.content;
+    let bytes = encoding::encode(&content, target, with_bom)?;
+    let hash = content_hash(&bytes);
+    let size = bytes.len() as u64;
+
+    if let Some(previous) = on_disk.filter(|d| d.version.hash != hash) {
+        history::record_snapshot(&app, path.to_string_lossy().to_string(), previous.bytes)
+            .await
+            .map_err(|message| SaveError::Io { message })?;
+    }
+
+    atomic_write_async(path.clone(), bytes).await?;
 
-    atomic_write_async(path.clone(), content)
-        .await
-        .map_err(|e| tauri::Error::Io(e))?;
+    let metadata = tokio::fs::metadata(&path).await?;
 
-    Ok(Some(format!("File saved at {}", path.to_string_lossy())))
+    Ok(SavedFile {
+        message: format!("File saved at {}", path.to_string_lossy()),
+        version: FileVersion {
+            modified: modified_millis(&metadata),
+            size,
+            hash,
+        },
+    })
 }
 
 #[tauri::command]
@@ -107,27 +340,94 @@ pub async fn open_file(path: PathBuf) -> Result<Option<OpenedFile>> {
 
     // Guard against accidentally opening huge files.
     let size = metadata.len();
-    if size > MAX_FILE_SIZE {
-        return Err(tauri::Error::Io(std::io::Error::new(
-            std::io::ErrorKind::InvalidData,
-            format!(
-                "File is too large ({:.1} MB). The maximum supported size is {:.0} MB.",
-                size as f64 / (1024.0 * 1024.0),
-                MAX_FILE_SIZE as f64 / (1024.0 * 1024.0),
-            ),
-        )));
-    }
+    check_size(size).map_err(tauri::Error::Io)?;
 
-    let content = tokio::fs::read_to_string(&path)
-        .await
-        .map_err(|e| tauri::Error::Io(e))?;
+    let bytes = tokio::fs::read(&path).await.map_err(tauri::Error::Io)?;
+    let decoded = encoding::decode(&bytes).map_err(tauri::Error::Io)?;
+    let content = decoded.content;
 
     let file_info = FileInfo {
         line_ending: detect_line_ending(&content).to_string(),
-        encoding: "UTF-8".to_string(),
+        encoding: decoded.encoding.label().to_string(),
+        has_bom: decoded.has_bom,
         file_size: size,
         extension: extract_extension(&path),
+        version: FileVersion::new(&metadata, &bytes),
     };
 
     Ok(Some(OpenedFile { content, file_info }))
 }
+
+/// Rewrite the line endings of encoded text, keeping its encoding and BOM.
+///
+/// Returns the new bytes, or `None` when nothing needed changing, and the
+/// number of line terminators rewritten.
+pub(crate) fn convert_line_ending_bytes(
+    bytes: &[u8],
+    target: LineEnding,
+) -> std::io::Result<(Option<Vec<u8>>, usize)> {
+    let decoded = encoding::decode(bytes)?;
+    let (content, changed_lines) = normalize(&decoded.content, target);
+
+    if changed_lines == 0 {
+        return Ok((None, 0));
+    }
+
+    let bytes = encoding::encode(&content, decoded.encoding, decoded.has_bom)?;
+    Ok((Some(bytes), changed_lines))
+}
+
+/// Convert the line endings of the file at `path` in place.
+///
+/// The file's encoding and BOM are kept. When nothing needs changing the file
+/// is left untouched; otherwise the previous contents are kept in the local
+/// version history, as on a save.
+#[tauri::command]
+pub async fn convert_line_endings(
+    app: tauri::AppHandle,
+    path: PathBuf,
+    target: LineEnding,
+) -> Result<LineEndingConversion> {
+    let metadata = tokio::fs::metadata(&path).await.map_err(tauri::Error::Io)?;
+    check_size(metadata.len()).map_err(tauri::Error::Io)?;
+
+    let bytes = tokio::fs::read(&path).await.map_err(tauri::Error::Io)?;
+    let (converted, changed_lines) =
+        convert_line_ending_bytes(&bytes, target).map_err(tauri::Error::Io)?;
+
+    if let Some(converted) = converted {
+        history::record_snapshot(&app, path.to_string_lossy().to_string(), bytes)
+            .await
+            .map_err(|e| tauri::Error::Io(std::io::Error::other(e)))?;
+
+        atomic_write_async(path, converted)
+            .await
+            .map_err(tauri::Error::Io)?;
+    }
+
+    Ok(LineEndingConversion {
+        line_ending: target.label().to_string(),
+        changed_lines,
+    })
+}
+
+async fn read_text(path: &Path) -> Result<String> {
+    let bytes = tokio::fs::read(path).await.map_err(tauri::Error::Io)?;
+    let decoded = encoding::decode(&bytes).map_err(tauri::Error::Io)?;
+    Ok(decoded.content)
+}
+
+/// Unified diff from `left` to `right`, both read from disk. Used for
+/// `--diff` on the command line.
+#[tauri::command]
+pub async fn diff_files(left: PathBuf, right: PathBuf) -> Result<String> {
+    let old = read_text(&left).await?;
+    let new = read_text(&right).await?;
+
+    let diff = TextDiff::from_lines(&old, &new)
+        .unified_diff()
+        .header(&left.to_string_lossy(), &right.to_string_lossy())
+        .to_string();
+
+    Ok(diff)
+}
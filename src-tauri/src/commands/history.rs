@@ -0,0 +1,143 @@
+use std::path::PathBuf;
+
+use similar::TextDiff;
+use tauri::command;
+
+use crate::commands::files::FileVersion;
+use crate::commands::migration::get_v2_data_dir;
+use crate::commands::settings::read_settings;
+use crate::snapshots::{self, Snapshot};
+use crate::{encoding, utils};
+
+pub(crate) fn get_history_dir(app: &tauri::AppHandle) -> PathBuf {
+    get_v2_data_dir(app).join("history")
+}
+
+/// Store `previous` as a snapshot of `path` before it gets overwritten.
+///
+/// The snapshot is on disk when this returns, so callers can refuse to
+/// write if it fails. The retention policy is applied in the background.
+pub(crate) async fn record_snapshot(
+    app: &tauri::AppHandle,
+    path: String,
+    previous: Vec<u8>,
+) -> Result<(), String> {
+    let root = get_history_dir(app);
+    tokio::task::spawn_blocking(move || snapshots::record_snapshot(&root, &path, &previous))
+        .await
+        .map_err(|e| format!("File history task panicked: {}", e))?
+        .map_err(|e| format!("Error recording file history: {}", e))?;
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let root = get_history_dir(&app);
+        let policy = match read_settings(&app).await {
+            Ok(settings) => settings.history,
+            Err(e) => {
+                eprintln!("Skipping history retention: {}", e);
+                return;
+            }
+        };
+
+        let result =
+            tokio::task::spawn_blocking(move || snapshots::enforce_retention(&root, policy)).await;
+
+        match result {
+            Ok(Err(e)) => eprintln!("Error applying history retention: {}", e),
+            Err(e) => eprintln!("History retention task panicked: {}", e),
+            Ok(Ok(())) => {}
+        }
+    });
+
+    Ok(())
+}
+
+async fn read_version_bytes(
+    app: &tauri::AppHandle,
+    path: String,
+    id: String,
+) -> Result<Vec<u8>, String> {
+    let root = get_history_dir(app);
+    tokio::task::spawn_blocking(move || snapshots::read_snapshot(&root, &path, &id))
+        .await
+        .map_err(|e| format!("History task panicked: {}", e))?
+}
+
+fn decode_version(bytes: &[u8]) -> Result<String, String> {
+    encoding::decode(bytes)
+        .map(|d| d.content)
+        .map_err(|e| format!("Error decoding version: {}", e))
+}
+
+/// List the stored versions of `path`, newest first.
+#[command]
+pub async fn list_file_versions(
+    app: tauri::AppHandle,
+    path: String,
+) -> Result<Vec<Snapshot>, String> {
+    let root = get_history_dir(&app);
+    tokio::task::spawn_blocking(move || snapshots::list_snapshots(&root, &path))
+        .await
+        .map_err(|e| format!("History task panicked: {}", e))
+}
+
+/// Return the text of one stored version of `path`.
+#[command]
+pub async fn read_file_version(
+    app: tauri::AppHandle,
+    path: String,
+    id: String,
+) -> Result<String, String> {
+    let bytes = read_version_bytes(&app, path, id).await?;
+    decode_version(&bytes)
+}
+
+/// Unified diff from a stored version of `path` to its current contents.
+#[command]
+pub async fn diff_file_version(
+    app: tauri::AppHandle,
+    path: String,
+    id: String,
+) -> Result<String, String> {
+    let old = decode_version(&read_version_bytes(&app, path.clone(), id.clone()).await?)?;
+
+    let current = tokio::fs::read(&path)
+        .await
+        .map_err(|e| format!("Error reading {}: {}", path, e))?;
+    let new = decode_version(&current)?;
+
+    let diff = TextDiff::from_lines(&old, &new)
+        .unified_diff()
+        .header(&format!("{} ({})", path, id), &path)
+        .to_string();
+
+    Ok(diff)
+}
+
+/// Replace `path` with a stored version, byte for byte.
+///
+/// The contents being replaced are snapshotted first, so a restore can
+/// itself be undone.
+#[command]
+pub async fn restore_file_version(
+    app: tauri::AppHandle,
+    path: String,
+    id: String,
+) -> Result<FileVersion, String> {
+    let bytes = read_version_bytes(&app, path.clone(), id).await?;
+
+    if let Ok(current) = tokio::fs::read(&path).await {
+        record_snapshot(&app, path.clone(), current).await?;
+    }
+
+    let target = PathBuf::from(&path);
+    utils::atomic_write_async(target.clone(), bytes.clone())
+        .await
+        .map_err(|e| format!("Error restoring {}: {}", path, e))?;
+
+    let metadata = tokio::fs::metadata(&target)
+        .await
+        .map_err(|e| format!("Error reading {}: {}", path, e))?;
+
+    Ok(FileVersion::new(&metadata, &bytes))
+}
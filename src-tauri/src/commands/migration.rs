@@ -1,13 +1,21 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
-use tauri::Manager;
 use tauri::command;
+use tauri::{Emitter, Manager};
+use tokio::sync::{MappedMutexGuard, MutexGuard};
 
+use crate::commands::files::FileVersion;
+use crate::commands::settings::{read_settings, set_notes_home};
+use crate::commands::windows::{self, MAIN_WINDOW};
+use crate::journal::{self, Journal};
+use crate::note_metadata::{self, MetadataStore, NoteMetadata};
 use crate::utils;
-use crate::utils::sanitize_filename;
 use crate::utils::validate_local_files;
+use crate::v1_migration::{self, MigrationPaths, MigrationPlan, MigrationReport, NoteRef};
+use crate::watcher::FileWatcher;
+use crate::{buffers, salvage, schema};
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -19,15 +27,17 @@ pub struct NoteV1 {
     pub file_extension: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SchemaVersion {
     V1,
     V2,
+    V3,
+    V4,
 }
 
-// V2 schemas
+// V4 schemas
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct SessionTab {
     pub id: String,
@@ -35,10 +45,76 @@ pub struct SessionTab {
     pub path: Option<String>, // None for untitled tabs
     pub filename: String,
     pub is_dirty: bool,
+    /// Text of untitled tabs, and unsaved edits of tabs with a path. The
+    /// latter is moved to a [`buffers`] file before the session is written.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub content: Option<String>, // Only stored for untitled tabs (path == None)
+    pub content: Option<String>,
+    /// Hash of the buffer holding `content` while the session is on disk.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buffer: Option<String>,
+    /// Version of the file the unsaved edits were made on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_version: Option<FileVersion>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub view: Option<ViewState>,
+}
+
+/// A selection as offsets into the text, in UTF-16 code units as the editor
+/// counts them. `anchor` is where it started and `head` where the cursor
+/// is, so `anchor == head` is a plain cursor.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelectionRange {
+    pub anchor: usize,
+    pub head: usize,
+}
+
+/// A folded block of lines, 1-based and inclusive.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FoldRange {
+    pub from_line: u32,
+    pub to_line: u32,
+}
+
+/// Where the user was in a document: selections (the first is the primary
+/// one), scroll offsets in pixels and folded blocks.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ViewState {
+    #[serde(default)]
+    pub selections: Vec<SelectionRange>,
+    #[serde(default)]
+    pub scroll_top: u32,
+    #[serde(default)]
+    pub scroll_left: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub folds: Vec<FoldRange>,
+}
+
+/// The view state last seen for a file, kept after its tab is closed.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RememberedView {
+    pub path: String,
+    #[serde(flatten)]
+    pub view: ViewState,
+}
+
+/// How many files [`DatabaseV4::remember_view`] keeps view state for.
+const MAX_REMEMBERED_VIEWS: usize = 200;
+
+/// A tab closed by the user, kept so it can be reopened.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ClosedTab {
+    #[serde(flatten)]
+    pub tab: SessionTab,
+    /// When the tab was closed, unix milliseconds.
+    pub closed_at: u64,
 }
 
+/// How many tabs [`DatabaseV4::push_closed_tab`] keeps.
+const MAX_CLOSED_TABS: usize = 30;
+
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct EditorSession {
@@ -47,32 +123,365 @@ pub struct EditorSession {
     pub current_tab_id: Option<String>,
 }
 
+/// Where a window was on screen, in physical pixels.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub maximized: bool,
+}
+
+/// A named set of tabs. Each window shows one workspace, whose tabs are
+/// that window's session; a workspace no window shows keeps them here.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Workspace {
+    pub id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parked: Option<EditorSession>,
+}
+
+/// The tabs and placement of one window.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowSession {
+    pub label: String,
+    /// Id of the workspace the window shows.
+    pub workspace: String,
+    #[serde(flatten)]
+    pub session: EditorSession,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub geometry: Option<WindowGeometry>,
+    /// When the window last saved its tabs, unix milliseconds. Journal
+    /// entries of the window newer than this are replayed on load.
+    #[serde(default)]
+    pub saved_at: u64,
+}
+
+impl WindowSession {
+    pub fn new(label: &str, workspace: String) -> Self {
+        WindowSession {
+            label: label.to_string(),
+            workspace,
+            session: EditorSession::default(),
+            geometry: None,
+            saved_at: 0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct LocalFile {
     pub id: String,
     pub filename: String,
     pub modified: u64, // unix timestamp in milliseconds
     pub path: String,
+    /// How many times the file was opened in a tab.
+    #[serde(default)]
+    pub open_count: u32,
+    /// When the file was last opened in a tab, unix milliseconds.
+    #[serde(default)]
+    pub last_opened: u64,
+    /// Pinned files are listed first and never pruned.
+    #[serde(default)]
+    pub pinned: bool,
+    /// The file wasn't found when the session was last loaded. Only pinned
+    /// files are kept while missing, as a drive may just be unmounted.
+    #[serde(default)]
+    pub missing: bool,
+}
+
+/// Time after which an open counts half as much towards the frecency score.
+const FRECENCY_HALF_LIFE_MS: f64 = 14.0 * 24.0 * 60.0 * 60.0 * 1000.0;
+
+impl LocalFile {
+    /// When the file was last opened or saved.
+    fn last_used(&self) -> u64 {
+        self.last_opened.max(self.modified)
+    }
+
+    /// How often and how recently the file was used: the number of opens,
+    /// halved for every [`FRECENCY_HALF_LIFE_MS`] since it was last used.
+    pub fn frecency(&self, now: u64) -> f64 {
+        let age = now.saturating_sub(self.last_used()) as f64;
+        self.open_count.max(1) as f64 * 0.5f64.powf(age / FRECENCY_HALF_LIFE_MS)
+    }
+}
+
+/// A recent file with its frecency score, as listed for the user.
+#[derive(Serialize, Debug, Clone)]
+pub struct RankedFile {
+    #[serde(flatten)]
+    pub file: LocalFile,
+    pub score: f64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
-pub struct DatabaseV2 {
+pub struct DatabaseV4 {
     pub recent_files: HashMap<String, LocalFile>, // path -> file info
-    pub session: EditorSession,
+    pub workspaces: Vec<Workspace>,
+    pub windows: Vec<WindowSession>,
+    /// Most recently used first, at most [`MAX_REMEMBERED_VIEWS`].
+    #[serde(default)]
+    pub remembered_views: Vec<RememberedView>,
+    /// Most recently closed first, at most [`MAX_CLOSED_TABS`].
+    #[serde(default)]
+    pub closed_tabs: Vec<ClosedTab>,
     pub schema_version: SchemaVersion,
 }
 
-impl Default for DatabaseV2 {
+impl Default for DatabaseV4 {
     fn default() -> Self {
-        DatabaseV2 {
+        DatabaseV4 {
             recent_files: HashMap::new(),
-            session: EditorSession::default(),
-            schema_version: SchemaVersion::V2,
+            workspaces: Vec::new(),
+            windows: Vec::new(),
+            remembered_views: Vec::new(),
+            closed_tabs: Vec::new(),
+            schema_version: schema::CURRENT_VERSION,
         }
     }
 }
 
+impl DatabaseV4 {
+    pub fn window(&self, label: &str) -> Option<&WindowSession> {
+        self.windows.iter().find(|w| w.label == label)
+    }
+
+    /// The session of window `label`, added empty on a new workspace if
+    /// there is none yet.
+    pub fn window_mut(&mut self, label: &str) -> &mut WindowSession {
+        let index = match self.windows.iter().position(|w| w.label == label) {
+            Some(index) => index,
+            None => {
+                let workspace = self.add_workspace(self.unused_workspace_name(), None);
+                self.windows.push(WindowSession::new(label, workspace));
+                self.windows.len() - 1
+            }
+        };
+        &mut self.windows[index]
+    }
+
+    pub fn workspace(&self, id: &str) -> Option<&Workspace> {
+        self.workspaces.iter().find(|w| w.id == id)
+    }
+
+    pub fn workspace_mut(&mut self, id: &str) -> Option<&mut Workspace> {
+        self.workspaces.iter_mut().find(|w| w.id == id)
+    }
+
+    /// Label of the window showing workspace `id`, if any.
+    pub fn shown_in(&self, id: &str) -> Option<&str> {
+        self.windows
+            .iter()
+            .find(|w| w.workspace == id)
+            .map(|w| w.label.as_str())
+    }
+
+    /// Add a workspace and return its id. `parked` is `None` when a window
+    /// is about to show it.
+    pub fn add_workspace(&mut self, name: String, parked: Option<EditorSession>) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+        self.workspaces.push(Workspace {
+            id: id.clone(),
+            name,
+            parked,
+        });
+        id
+    }
+
+    /// "Default" for the first workspace, then the first of "Workspace 1",
+    /// "Workspace 2", ... not taken yet.
+    pub fn unused_workspace_name(&self) -> String {
+        if self.workspaces.is_empty() {
+            return "Default".to_string();
+        }
+
+        (1..)
+            .map(|n| format!("Workspace {}", n))
+            .find(|name| self.workspaces.iter().all(|w| &w.name != name))
+            .unwrap_or_default()
+    }
+
+    /// Give every window a workspace of its own. A window whose workspace is
+    /// missing, parked or already shown elsewhere gets a new one, so no tabs
+    /// are lost when the file was edited by hand or salvaged.
+    pub fn repair_workspaces(&mut self) {
+        let mut claimed = Vec::new();
+
+        for index in 0..self.windows.len() {
+            let id = &self.windows[index].workspace;
+            let valid =
+                !claimed.contains(id) && self.workspace(id).is_some_and(|w| w.parked.is_none());
+
+            if !valid {
+                let name = self.unused_workspace_name();
+                self.windows[index].workspace = self.add_workspace(name, None);
+            }
+            claimed.push(self.windows[index].workspace.clone());
+        }
+    }
+
+    /// The tabs of every window and every parked workspace.
+    pub fn sessions_mut(&mut self) -> impl Iterator<Item = &mut EditorSession> {
+        let parked = self.workspaces.iter_mut().filter_map(|w| w.parked.as_mut());
+        self.windows
+            .iter_mut()
+            .map(|w| &mut w.session)
+            .chain(parked)
+    }
+
+    /// Remember `view` for `path` as the most recently used, forgetting the
+    /// least recently used file once there are too many.
+    pub fn remember_view(&mut self, path: String, view: ViewState) {
+        self.remembered_views.retain(|r| r.path != path);
+        self.remembered_views
+            .insert(0, RememberedView { path, view });
+        self.remembered_views.truncate(MAX_REMEMBERED_VIEWS);
+    }
+
+    pub fn remembered_view(&self, path: &str) -> Option<&ViewState> {
+        self.remembered_views
+            .iter()
+            .find(|r| r.path == path)
+            .map(|r| &r.view)
+    }
+
+    /// Put `tab` on top of the closed tabs, dropping the oldest once there
+    /// are too many. A file tab is reopened from disk, so its unsaved edits,
+    /// which the user chose not to save, are not kept.
+    pub fn push_closed_tab(&mut self, mut tab: SessionTab) {
+        if tab.path.is_some() {
+            tab.is_dirty = false;
+            tab.content = None;
+            tab.base_version = None;
+            self.closed_tabs.retain(|c| c.tab.path != tab.path);
+        }
+        tab.buffer = None;
+        self.closed_tabs.retain(|c| c.tab.id != tab.id);
+
+        self.closed_tabs.insert(
+            0,
+            ClosedTab {
+                tab,
+                closed_at: chrono::Utc::now().timestamp_millis() as u64,
+            },
+        );
+        self.closed_tabs.truncate(MAX_CLOSED_TABS);
+    }
+
+    /// Remove closed tab `id` from the stack, or the most recently closed
+    /// one when `None`.
+    pub fn take_closed_tab(&mut self, id: Option<&str>) -> Option<ClosedTab> {
+        let index = match id {
+            Some(id) => self.closed_tabs.iter().position(|c| c.tab.id == id)?,
+            None if self.closed_tabs.is_empty() => return None,
+            None => 0,
+        };
+        Some(self.closed_tabs.remove(index))
+    }
+
+    /// Count an open of the file at `path`, adding it to the recent files if
+    /// needed.
+    pub fn record_open(&mut self, path: String, now: u64) -> &LocalFile {
+        let file = self
+            .recent_files
+            .entry(path.clone())
+            .or_insert_with(|| LocalFile {
+                id: uuid::Uuid::new_v4().to_string(),
+                filename: Path::new(&path)
+                    .file_name()
+                    .map_or_else(|| path.clone(), |name| name.to_string_lossy().into_owned()),
+                modified: now,
+                path,
+                open_count: 0,
+                last_opened: 0,
+                pinned: false,
+                missing: false,
+            });
+        file.open_count = file.open_count.saturating_add(1);
+        file.missing = false;
+        file.last_opened = now;
+        file.modified = file.modified.max(now);
+        file
+    }
+
+    /// The recent files, pinned ones first, each group by descending
+    /// frecency.
+    pub fn ranked_recent_files(&self, now: u64) -> Vec<RankedFile> {
+        let mut ranked: Vec<RankedFile> = self
+            .recent_files
+            .values()
+            .map(|file| RankedFile {
+                file: file.clone(),
+                score: file.frecency(now),
+            })
+            .collect();
+
+        ranked.sort_by(|a, b| {
+            b.file
+                .pinned
+                .cmp(&a.file.pinned)
+                .then(b.score.total_cmp(&a.score))
+                .then(b.file.last_used().cmp(&a.file.last_used()))
+        });
+        ranked
+    }
+
+    /// Forget the lowest ranked recent files beyond the first `max` that
+    /// aren't pinned. `0` disables the limit.
+    pub fn prune_recent_files(&mut self, max: usize, now: u64) {
+        if max == 0 {
+            return;
+        }
+
+        let pruned: Vec<String> = self
+            .ranked_recent_files(now)
+            .into_iter()
+            .filter(|r| !r.file.pinned)
+            .skip(max)
+            .map(|r| r.file.path)
+            .collect();
+
+        for path in pruned {
+            self.recent_files.remove(&path);
+        }
+    }
+
+    /// Every tab of every window and every parked workspace.
+    pub fn tabs(&self) -> impl Iterator<Item = &SessionTab> {
+        let parked = self.workspaces.iter().filter_map(|w| w.parked.as_ref());
+        self.windows
+            .iter()
+            .map(|w| &w.session)
+            .chain(parked)
+            .flat_map(|s| &s.tabs)
+    }
+}
+
+/// What one window loads and saves: its own tabs plus the shared recent
+/// files.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowState {
+    pub recent_files: HashMap<String, LocalFile>,
+    pub session: EditorSession,
+}
+
+/// File names inside the app's data dirs, shared with the headless CLI.
+pub(crate) const V1_MANAGER_FILE: &str = "notes-manager.json";
+pub(crate) const SESSION_FILE: &str = "session.json";
+pub(crate) const JOURNAL_FILE: &str = "journal.jsonl";
+pub(crate) const BUFFERS_DIR: &str = "buffers";
+
+/// Folder created in the documents dir when there is no notes home yet.
+pub(crate) const DEFAULT_NOTES_FOLDER: &str = "taking-notes-app-notes";
+
 fn get_v1_local_data_dir(app: &tauri::AppHandle) -> PathBuf {
     app.path()
         .app_local_data_dir()
@@ -80,26 +489,34 @@ fn get_v1_local_data_dir(app: &tauri::AppHandle) -> PathBuf {
 }
 
 fn get_v1_manager_path(app: &tauri::AppHandle) -> PathBuf {
-    get_v1_local_data_dir(app).join("notes-manager.json")
+    get_v1_local_data_dir(app).join(V1_MANAGER_FILE)
 }
 
-fn get_v2_data_dir(app: &tauri::AppHandle) -> PathBuf {
+pub(crate) fn get_v2_data_dir(app: &tauri::AppHandle) -> PathBuf {
     app.path()
         .app_data_dir()
         .expect("error trying to get app data dir")
 }
 
 fn get_v2_manager_path(app: &tauri::AppHandle) -> PathBuf {
-    get_v2_data_dir(app).join("session.json")
+    get_v2_data_dir(app).join(SESSION_FILE)
 }
 
-fn get_desktop_migration_folder(app: &tauri::AppHandle) -> Result<PathBuf, String> {
-    let desktop = app
+fn get_buffers_dir(app: &tauri::AppHandle) -> PathBuf {
+    get_v2_data_dir(app).join(BUFFERS_DIR)
+}
+
+pub(crate) fn get_journal_path(app: &tauri::AppHandle) -> PathBuf {
+    get_v2_data_dir(app).join(JOURNAL_FILE)
+}
+
+fn get_default_notes_home(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let documents = app
         .path()
-        .desktop_dir()
-        .map_err(|e| format!("Error getting desktop dir: {}", e))?;
+        .document_dir()
+        .map_err(|e| format!("Error getting documents dir: {}", e))?;
 
-    Ok(desktop.join("taking-notes-app-notes"))
+    Ok(documents.join(DEFAULT_NOTES_FOLDER))
 }
 
 /// Check whether V1 data exists and a migration to V2 is needed.
@@ -144,181 +561,647 @@ pub async fn check_for_migration_to_v2(app: tauri::AppHandle) -> Result<bool, St
     }
 }
 
+/// Paths for a migration into `destination`, or into the notes home (falling
+/// back to the documents dir) when none is given.
+async fn migration_paths(
+    app: &tauri::AppHandle,
+    destination: Option<String>,
+) -> Result<MigrationPaths, String> {
+    let notes_home = read_settings(app).await?.notes_home.map(PathBuf::from);
+    let destination = v1_migration::choose_destination(destination, notes_home.as_deref(), || {
+        get_default_notes_home(app)
+    })?;
+
+    Ok(MigrationPaths::new(
+        get_v1_local_data_dir(app),
+        get_v2_data_dir(app),
+        destination,
+        notes_home,
+    ))
+}
+
+/// Report what [`migrate_v1_to_v2`] would do: where each note would go,
+/// which names collide and which V1 files are missing. Nothing is written.
+///
+/// `destination` defaults to the notes home, or the documents dir on first use.
+#[command]
+pub async fn dry_run_v1_to_v2(
+    app: tauri::AppHandle,
+    destination: Option<String>,
+) -> Result<MigrationPlan, String> {
+    let paths = migration_paths(&app, destination).await?;
+
+    tokio::task::spawn_blocking(move || v1_migration::plan(&paths))
+        .await
+        .map_err(|e| format!("Migration task panicked: {}", e))?
+}
+
 /// Migrate from V1 to V2.
 ///
 /// This is a one-time bulk operation that copies many files sequentially, so
 /// the entire body runs inside `spawn_blocking` to keep the async runtime free.
+/// Title collisions get a numbered suffix, every copy is checksum-verified and
+/// a manifest is written before any V1 data is removed, so the migration can
+/// be undone with [`rollback_v1_to_v2`].
+///
+/// A `migration-progress` event is emitted after each note. `destination`
+/// works as in [`dry_run_v1_to_v2`] and is remembered as the notes home once
+/// the migration succeeds.
 #[command]
-pub async fn migrate_v1_to_v2(app: tauri::AppHandle) -> Result<String, String> {
-    let v1_manager_path = get_v1_manager_path(&app);
-    let v1_data_dir = get_v1_local_data_dir(&app);
+pub async fn migrate_v1_to_v2(
+    app: tauri::AppHandle,
+    destination: Option<String>,
+) -> Result<MigrationReport, String> {
+    let paths = migration_paths(&app, destination).await?;
+
+    let emitter = app.clone();
+    let report = tokio::task::spawn_blocking(move || {
+        v1_migration::migrate(&paths, |progress| {
+            let _ = emitter.emit("migration-progress", progress);
+        })
+    })
+    .await
+    .map_err(|e| format!("Migration task panicked: {}", e))??;
+
+    // The migration wrote a new session file.
+    app.state::<SessionState>().reset().await;
+
+    if let Err(e) = set_notes_home(&app, report.destination.clone()).await {
+        eprintln!("Warning: could not remember the notes home: {}", e);
+    }
+
+    Ok(report)
+}
+
+/// Paths for retrying the notes the last migration couldn't copy: same
+/// destination as that migration.
+async fn retry_paths(app: &tauri::AppHandle) -> Result<MigrationPaths, String> {
+    let v2_data_dir = get_v2_data_dir(app);
+    let destination = tokio::task::spawn_blocking({
+        let v2_data_dir = v2_data_dir.clone();
+        move || v1_migration::read_manifest(&v2_data_dir).map(|m| m.destination)
+    })
+    .await
+    .map_err(|e| format!("Migration task panicked: {}", e))??;
+    let notes_home = read_settings(app).await?.notes_home.map(PathBuf::from);
+
+    Ok(MigrationPaths::new(
+        get_v1_local_data_dir(app),
+        v2_data_dir,
+        PathBuf::from(destination),
+        notes_home,
+    ))
+}
+
+/// The V1 notes the last migration couldn't copy, which
+/// [`retry_failed_v1_notes`] can try again. Empty when there are none.
+#[command]
+pub async fn list_failed_v1_notes(app: tauri::AppHandle) -> Result<Vec<NoteRef>, String> {
+    let Ok(paths) = retry_paths(&app).await else {
+        return Ok(Vec::new());
+    };
+
+    tokio::task::spawn_blocking(move || v1_migration::failed_notes(&paths))
+        .await
+        .map_err(|e| format!("Migration task panicked: {}", e))
+}
+
+/// Copy the V1 notes the last migration couldn't, into the same folder, and
+/// add them to the recent files. Emits `migration-progress` like
+/// [`migrate_v1_to_v2`]; notes that fail again can be retried later.
+#[command]
+pub async fn retry_failed_v1_notes(app: tauri::AppHandle) -> Result<MigrationReport, String> {
+    let paths = retry_paths(&app).await?;
+
+    let emitter = app.clone();
+    let (report, recent_files) = tokio::task::spawn_blocking(move || {
+        v1_migration::retry_failed(&paths, |progress| {
+            let _ = emitter.emit("migration-progress", progress);
+        })
+    })
+    .await
+    .map_err(|e| format!("Migration task panicked: {}", e))??;
+
+    if !recent_files.is_empty() {
+        update_session(&app, |db| {
+            for (path, file) in recent_files {
+                db.recent_files.entry(path).or_insert(file);
+            }
+            Ok(())
+        })
+        .await?;
+    }
+
+    Ok(report)
+}
+
+/// Undo the last V1 → V2 migration using its manifest.
+///
+/// Notes are moved back to their V1 location and the V1 manager is restored;
+/// the V2 session is kept aside as `session.rolled-back-<timestamp>.json`.
+#[command]
+pub async fn rollback_v1_to_v2(app: tauri::AppHandle) -> Result<String, String> {
     let v2_data_dir = get_v2_data_dir(&app);
     let v2_manager_path = get_v2_manager_path(&app);
-    let desktop_folder = get_desktop_migration_folder(&app)?;
 
-    tokio::task::spawn_blocking(move || {
-        use std::fs;
+    let restored =
+        tokio::task::spawn_blocking(move || v1_migration::rollback(&v2_data_dir, &v2_manager_path))
+            .await
+            .map_err(|e| format!("Rollback task panicked: {}", e))??;
 
-        // Verify V1 exists.
-        if !v1_manager_path.exists() {
-            return Err("No V1 data found to migrate".to_string());
-        }
+    // The session file was moved aside.
+    app.state::<SessionState>().reset().await;
 
-        // Read V1 data.
-        let data_v1 = fs::read_to_string(&v1_manager_path)
-            .map_err(|e| format!("Error reading V1 manager: {}", e))?;
+    Ok(format!("Rollback successful! {} notes restored", restored))
+}
 
-        let notes_v1: Vec<NoteV1> =
-            serde_json::from_str(&data_v1).map_err(|e| format!("Error parsing V1 data: {}", e))?;
+/// Title, tag and dates a note had in V1, if `path` was migrated from V1.
+#[command]
+pub async fn get_note_metadata(
+    app: tauri::AppHandle,
+    path: String,
+) -> Result<Option<NoteMetadata>, String> {
+    let store_path = note_metadata::metadata_path(&get_v2_data_dir(&app));
 
-        // Create the desktop migration folder.
-        fs::create_dir_all(&desktop_folder)
-            .map_err(|e| format!("Error creating desktop folder: {}", e))?;
+    tokio::task::spawn_blocking(move || note_metadata::read_store(&store_path).remove(&path))
+        .await
+        .map_err(|e| format!("Metadata task panicked: {}", e))
+}
 
-        // Create V2 data directory if needed.
-        fs::create_dir_all(&v2_data_dir)
-            .map_err(|e| format!("Error creating V2 data dir: {}", e))?;
+/// Every migrated note's V1 metadata, keyed by path.
+#[command]
+pub async fn list_note_metadata(app: tauri::AppHandle) -> Result<MetadataStore, String> {
+    let store_path = note_metadata::metadata_path(&get_v2_data_dir(&app));
 
-        let mut recent_files: HashMap<String, LocalFile> = HashMap::new();
-        let mut migrated_count = 0;
+    tokio::task::spawn_blocking(move || note_metadata::read_store(&store_path))
+        .await
+        .map_err(|e| format!("Metadata task panicked: {}", e))
+}
 
-        for note in notes_v1.into_iter() {
-            let old_filename = format!("{}.{}", note.tag, note.file_extension);
-            let src = v1_data_dir.join(&old_filename);
+/// Something the user should know about after loading the session.
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum LoadWarning {
+    /// `session.json` couldn't be parsed. It was moved to `backup_path` and
+    /// whatever could be salvaged from it was loaded instead.
+    #[serde(rename_all = "camelCase")]
+    SessionQuarantined {
+        backup_path: Option<String>,
+        error: String,
+        recovered_tabs: usize,
+        recovered_recent_files: usize,
+    },
+    /// Unsaved edits newer than the session file were restored from the journal.
+    RecoveredFromJournal { tabs: usize },
+    /// These files changed on disk, or were deleted, after their restored
+    /// unsaved edits were made.
+    ChangedOnDisk { paths: Vec<String> },
+    /// The unsaved edits of these files couldn't be read back, so their tabs
+    /// show the file as it is on disk.
+    UnsavedEditsLost { paths: Vec<String> },
+}
 
-            if src.exists() {
-                let title_sanitized = sanitize_filename(&note.title);
-                let new_filename = format!("{}.{}", title_sanitized, note.file_extension);
-                let dest = desktop_folder.join(&new_filename);
+/// Result of `load_editor_state`: the window's state plus any warnings. The
+/// state is flattened so the shape stays compatible with `WindowState`.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadedEditorState {
+    #[serde(flatten)]
+    pub state: WindowState,
+    pub warnings: Vec<LoadWarning>,
+}
 
-                fs::copy(&src, &dest)
-                    .map_err(|e| format!("Failed to copy file {:?} -> {:?}: {}", src, dest, e))?;
+/// Why the session file couldn't be loaded. Only a corrupt file is
+/// quarantined; on any other error the load fails and the file is left as is.
+#[derive(Debug)]
+pub(crate) enum SessionError {
+    /// The contents don't decode as any known session format.
+    Corrupt(String),
+    /// The file names a schema version this build doesn't know, most likely
+    /// because a newer version of the app wrote it.
+    Unsupported(String),
+    /// Reading, backing up or writing back the file failed.
+    Io(String),
+}
 
-                let metadata = fs::metadata(&dest)
-                    .map_err(|e| format!("Error getting metadata for {:?}: {}", dest, e))?;
+impl std::fmt::Display for SessionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionError::Corrupt(e) | SessionError::Unsupported(e) | SessionError::Io(e) => {
+                f.write_str(e)
+            }
+        }
+    }
+}
 
-                let mut modified: u64 = 0;
-                if let Ok(time) = metadata.modified() {
-                    if let Ok(duration) = time.duration_since(std::time::UNIX_EPOCH) {
-                        modified = duration.as_millis() as u64;
-                    }
-                }
+/// Parse session file contents in memory, upgrading them through the
+/// [`schema`] pipeline. Returns the data and the version it was stored as.
+pub(crate) fn decode_session(raw: &str) -> Result<(DatabaseV4, SchemaVersion), SessionError> {
+    let value: serde_json::Value =
+        serde_json::from_str(raw).map_err(|e| SessionError::Corrupt(e.to_string()))?;
 
-                let path_string = dest.to_string_lossy().to_string();
-                recent_files.insert(
-                    path_string.clone(),
-                    LocalFile {
-                        id: note.tag,
-                        filename: new_filename,
-                        path: path_string,
-                        modified,
-                    },
-                );
-
-                migrated_count += 1;
-            }
+    if value.get("schemaVersion").is_some() {
+        schema::detect_version(&value).map_err(SessionError::Unsupported)?;
+    }
+
+    let (value, from) = schema::migrate_to_current(value).map_err(SessionError::Corrupt)?;
+    let db: DatabaseV4 =
+        serde_json::from_value(value).map_err(|e| SessionError::Corrupt(e.to_string()))?;
+    Ok((db, from))
+}
+
+/// Parse the session file, upgrading it through the [`schema`] pipeline when
+/// it was written by an older version of the app.
+///
+/// Before an upgraded file is written back, the original is copied to
+/// `session.<version>-backup-<timestamp>.json` next to it.
+async fn parse_session(path: &Path, raw: &str) -> Result<DatabaseV4, SessionError> {
+    let (db, from) = decode_session(raw)?;
+
+    if from != schema::CURRENT_VERSION {
+        let backup = path.with_file_name(format!(
+            "session.{:?}-backup-{}.json",
+            from,
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        tokio::fs::copy(path, &backup).await.map_err(|e| {
+            SessionError::Io(format!("Error backing up session before migration: {}", e))
+        })?;
+
+        let serialized = serde_json::to_string_pretty(&db)
+            .map_err(|e| SessionError::Io(format!("Serialization error: {}", e)))?;
+        utils::atomic_write_async(path.to_path_buf(), serialized)
+            .await
+            .map_err(|e| SessionError::Io(format!("Error writing migrated session file: {}", e)))?;
+    }
+
+    Ok(db)
+}
+
+/// Move a corrupt session file aside and salvage what we can from it, so the
+/// next `save_editor_state` doesn't silently replace the user's data.
+fn quarantine_session(path: &Path, raw: &str, error: String) -> (DatabaseV4, LoadWarning) {
+    let backup_path = match salvage::quarantine(path) {
+        Ok(p) => Some(p.to_string_lossy().to_string()),
+        Err(e) => {
+            eprintln!("Warning: could not move corrupt session file aside: {}", e);
+            None
         }
+    };
 
-        // Build V2 database.
-        let db_v2 = DatabaseV2 {
-            recent_files,
-            session: EditorSession::default(),
-            schema_version: SchemaVersion::V2,
-        };
+    let salvaged = salvage::salvage(raw);
 
-        let serialized = serde_json::to_string_pretty(&db_v2)
-            .map_err(|e| format!("Error serializing V2 data: {}", e))?;
+    let warning = LoadWarning::SessionQuarantined {
+        backup_path,
+        error,
+        recovered_tabs: salvaged.recovered_tabs,
+        recovered_recent_files: salvaged.recovered_recent_files,
+    };
 
-        utils::atomic_write(&v2_manager_path, &serialized)
-            .map_err(|e| format!("Error saving V2 session.json: {}", e))?;
+    (salvaged.db, warning)
+}
+
+/// The session shared by every window, read from disk by the first window to
+/// load. Each window only saves its own tabs, which are merged in here before
+/// the whole session is written back.
+#[derive(Default)]
+pub struct SessionState(tokio::sync::Mutex<Option<SharedSession>>);
 
-        // Clean up V1 directory.
-        if let Err(e) = fs::remove_dir_all(&v1_data_dir) {
-            let _ = fs::remove_file(&v1_manager_path);
-            eprintln!("Warning: Could not fully clean V1 directory: {}", e);
+struct SharedSession {
+    db: DatabaseV4,
+    /// Problems found while reading the session, not yet shown to the user.
+    warnings: Vec<LoadWarning>,
+}
+
+impl SessionState {
+    /// Forget the loaded session so the next load reads the file again.
+    async fn reset(&self) {
+        *self.0.lock().await = None;
+    }
+
+    /// Lock the session, reading it from disk first if no window has loaded
+    /// it yet.
+    async fn lock(
+        &self,
+        app: &tauri::AppHandle,
+    ) -> Result<MappedMutexGuard<'_, SharedSession>, String> {
+        let mut guard = self.0.lock().await;
+        if guard.is_none() {
+            *guard = Some(read_session(app).await?);
         }
 
-        let migration_message = format!(
-            "Migration successful! {} notes moved to: {}",
-            migrated_count,
-            desktop_folder.display()
-        );
+        MutexGuard::try_map(guard, Option::as_mut).map_err(|_| "Session not loaded".to_string())
+    }
+}
+
+/// Read the session shared by every window.
+pub(crate) async fn inspect_session<T>(
+    app: &tauri::AppHandle,
+    inspect: impl FnOnce(&DatabaseV4) -> T,
+) -> Result<T, String> {
+    let shared = app.state::<SessionState>().inner().lock(app).await?;
+    Ok(inspect(&shared.db))
+}
+
+/// Apply `change` to the session shared by every window and write the whole
+/// session back. Nothing is written when `change` fails, so it should check
+/// its arguments before touching the session.
+///
+/// Unsaved edits of file tabs are written to [`buffers`] rather than into
+/// the session file. The writes happen on a blocking thread so the async
+/// runtime stays responsive.
+pub(crate) async fn update_session<T>(
+    app: &tauri::AppHandle,
+    change: impl FnOnce(&mut DatabaseV4) -> Result<T, String>,
+) -> Result<T, String> {
+    let mut shared = app.state::<SessionState>().inner().lock(app).await?;
+    let db = &mut shared.db;
+    let result = change(db)?;
+
+    // The backend owns the stored format; whatever the frontend echoes back
+    // is stamped with the version this build writes.
+    db.schema_version = schema::CURRENT_VERSION;
+
+    // Unsaved edits of file tabs go to their buffers first so the session
+    // never refers to a buffer that isn't there.
+    let stored = db.clone();
+    let buffers_dir = get_buffers_dir(app);
+    let manager_path = get_v2_manager_path(app);
+
+    tokio::task::spawn_blocking(move || {
+        let stored = buffers::store(&buffers_dir, stored)
+            .map_err(|e| format!("Error writing unsaved edits: {}", e))?;
+        let serialized = serde_json::to_string_pretty(&stored)
+            .map_err(|e| format!("Serialization error: {}", e))?;
 
-        Ok(migration_message)
+        utils::atomic_write(&manager_path, serialized)
+            .map_err(|e| format!("Error writing session file: {}", e))?;
+
+        buffers::prune(&buffers_dir, &stored);
+        Ok::<_, String>(())
     })
     .await
-    .map_err(|e| format!("Migration task panicked: {}", e))?
+    .map_err(|e| format!("Session write task panicked: {}", e))??;
+
+    Ok(result)
 }
 
-/// Load the persisted editor state from disk.
+/// Read the session of every window from disk.
 ///
 /// The session file is read with `tokio::fs` and validation of referenced
-/// local files runs concurrently via [`validate_local_files`].
-#[command]
-pub async fn load_editor_state(app: tauri::AppHandle) -> Result<DatabaseV2, String> {
-    let manager_path = get_v2_manager_path(&app);
+/// local files runs concurrently via [`validate_local_files`]. A session file
+/// that fails to decode is quarantined rather than discarded; one that can't
+/// be read or comes from a newer version fails the load untouched, so a
+/// later save can't replace it.
+///
+/// The other windows saved in the session are reopened here, so they come
+/// back however the session was first read.
+async fn read_session(app: &tauri::AppHandle) -> Result<SharedSession, String> {
+    let manager_path = get_v2_manager_path(app);
+    let mut warnings = Vec::new();
 
-    let db = if tokio::fs::metadata(&manager_path).await.is_err() {
-        // File does not exist — return defaults.
-        DatabaseV2::default()
-    } else {
-        let raw = tokio::fs::read_to_string(&manager_path)
-            .await
-            .map_err(|e| format!("Failed reading manager file: {}", e))?;
+    let (mut db, session_modified) = match tokio::fs::metadata(&manager_path).await {
+        // File does not exist — start from defaults.
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => (DatabaseV4::default(), 0),
+        Err(e) => return Err(format!("Failed reading manager file: {}", e)),
+        Ok(metadata) => {
+            let bytes = tokio::fs::read(&manager_path)
+                .await
+                .map_err(|e| format!("Failed reading manager file: {}", e))?;
+            let raw = String::from_utf8_lossy(&bytes);
 
-        match serde_json::from_str::<DatabaseV2>(&raw) {
-            Ok(mut db) => {
-                // Runs all metadata checks concurrently.
-                validate_local_files(&mut db).await;
-                db
-            }
-            Err(e) => {
-                eprintln!("Error parsing V2 database: {}, using default", e);
-                DatabaseV2::default()
-            }
+            let db = match parse_session(&manager_path, &raw).await {
+                Ok(db) => db,
+                Err(SessionError::Corrupt(e)) => {
+                    eprintln!("Error parsing V4 database: {}, salvaging", e);
+                    let (db, warning) = quarantine_session(&manager_path, &raw, e);
+                    warnings.push(warning);
+                    db
+                }
+                Err(e) => return Err(format!("Error loading session file: {}", e)),
+            };
+
+            (db, utils::modified_millis(&metadata))
         }
     };
 
-    #[cfg(dev)]
+    db.repair_workspaces();
+
+    let buffers_dir = get_buffers_dir(app);
+    let (mut db, lost) = tokio::task::spawn_blocking(move || {
+        let lost = buffers::load(&buffers_dir, &mut db);
+        (db, lost)
+    })
+    .await
+    .map_err(|e| format!("Buffer load task panicked: {}", e))?;
+    if !lost.is_empty() {
+        warnings.push(LoadWarning::UnsavedEditsLost { paths: lost });
+    }
+
+    // Recover edits made after each window last saved.
+    let journal = app.state::<Journal>().inner().clone();
+    let entries = tokio::task::spawn_blocking(move || journal.read_entries())
+        .await
+        .unwrap_or_default();
+    let recovered = journal::replay(&mut db, entries, session_modified);
+    if recovered > 0 {
+        warnings.push(LoadWarning::RecoveredFromJournal { tabs: recovered });
+    }
+
+    // Runs all metadata checks concurrently.
+    validate_local_files(&mut db).await;
+
+    let changed = buffers::changed_on_disk(&db).await;
+    if !changed.is_empty() {
+        warnings.push(LoadWarning::ChangedOnDisk { paths: changed });
+    }
+
+    // The last window to close keeps its tabs; it comes back as the main one.
+    if db.window(MAIN_WINDOW).is_none()
+        && let Some(first) = db.windows.first_mut()
     {
-        println!("==========Successfully loaded editor state==========");
-        println!("Recent files count: {}", db.recent_files.len());
-        println!("Tabs count: {}", db.session.tabs.len());
-        println!("Schema version: {:?}", db.schema_version);
-        println!();
+        first.label = MAIN_WINDOW.to_string();
     }
 
-    Ok(db)
+    windows::restore_windows(app, &db);
+
+    Ok(SharedSession { db, warnings })
 }
 
-/// Persist the current editor state to disk.
+/// Load the calling window's tabs and the recent files.
 ///
-/// Uses [`atomic_write_async`](utils::atomic_write_async) so the write
-/// happens on a blocking thread and the async runtime stays responsive.
+/// The first window to load reads the session from disk and reopens the
+/// other windows saved in it. Load warnings are reported to the main window.
 #[command]
-pub async fn save_editor_state(app: tauri::AppHandle, state: DatabaseV2) -> Result<(), String> {
+pub async fn load_editor_state(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+) -> Result<LoadedEditorState, String> {
+    let session_state = app.state::<SessionState>();
+    let mut guard = session_state.0.lock().await;
+
+    let shared = match &mut *guard {
+        Some(shared) => shared,
+        empty => {
+            let loaded = read_session(&app).await?;
+            empty.insert(loaded)
+        }
+    };
+
+    let label = window.label();
+    let session = shared.db.window_mut(label).session.clone();
+    let warnings = if label == MAIN_WINDOW {
+        std::mem::take(&mut shared.warnings)
+    } else {
+        Vec::new()
+    };
+
+    app.state::<FileWatcher>().watch_window_paths(
+        label,
+        session.tabs.iter().filter_map(|t| t.path.clone()).collect(),
+    );
+
     #[cfg(dev)]
     {
-        println!("Attempting to save editor state");
+        println!("==========Successfully loaded editor state==========");
+        println!("Window: {}", label);
+        println!("Recent files count: {}", shared.db.recent_files.len());
+        println!("Tabs count: {}", session.tabs.len());
+        println!("Schema version: {:?}", shared.db.schema_version);
+        println!();
     }
 
-    let manager_path = get_v2_manager_path(&app);
+    Ok(LoadedEditorState {
+        state: WindowState {
+            recent_files: shared.db.recent_files.clone(),
+            session,
+        },
+        warnings,
+    })
+}
 
-    let serialized =
-        serde_json::to_string_pretty(&state).map_err(|e| format!("Serialization error: {}", e))?;
+/// What a window sends to be saved, ready to apply to the session with
+/// [`WindowSave::apply`].
+pub(crate) struct WindowSave {
+    state: WindowState,
+    geometry: Option<WindowGeometry>,
+    max_recent_files: usize,
+    /// When the save was taken; stamped into the window as `saved_at`.
+    pub(crate) now: u64,
+}
 
-    utils::atomic_write_async(manager_path, serialized)
-        .await
-        .map_err(|e| format!("Error writing session file: {}", e))?;
+impl WindowSave {
+    pub(crate) async fn new(
+        app: &tauri::AppHandle,
+        window: &tauri::WebviewWindow,
+        state: WindowState,
+    ) -> Self {
+        WindowSave {
+            state,
+            geometry: windows::geometry_of(window),
+            // Without the limit, keep every recent file rather than guess.
+            max_recent_files: read_settings(app)
+                .await
+                .map(|settings| settings.max_recent_files)
+                .unwrap_or_else(|e| {
+                    eprintln!("Warning: {}", e);
+                    0
+                }),
+            now: chrono::Utc::now().timestamp_millis() as u64,
+        }
+    }
+
+    /// Store the tabs and recent files of window `label` in `db`.
+    ///
+    /// Recent files are merged with those of the other windows, keeping the
+    /// newest entry per path.
+    pub(crate) fn apply(self, db: &mut DatabaseV4, label: &str) {
+        let WindowSave {
+            state,
+            geometry,
+            max_recent_files,
+            now,
+        } = self;
+
+        for (path, mut file) in state.recent_files {
+            match db.recent_files.get(&path) {
+                Some(known) if known.modified > file.modified => {}
+                known => {
+                    // Opens and pins are recorded by the backend; the
+                    // window's copy of them may be stale.
+                    if let Some(known) = known {
+                        file.open_count = known.open_count;
+                        file.last_opened = known.last_opened;
+                        file.pinned = known.pinned;
+                    }
+                    db.recent_files.insert(path, file);
+                }
+            }
+        }
+        db.prune_recent_files(max_recent_files, now);
+
+        // Open files are remembered as well, so opening one in another
+        // window or workspace picks up where it was left.
+        for tab in &state.session.tabs {
+            if let (Some(path), Some(view)) = (&tab.path, &tab.view) {
+                db.remember_view(path.clone(), view.clone());
+            }
+        }
+
+        let saved = db.window_mut(label);
+        saved.session = state.session;
+        saved.geometry = geometry.or(saved.geometry);
+        saved.saved_at = now;
 
+        #[cfg(dev)]
+        {
+            println!("==========Saving editor state==========");
+            println!("Window: {}", label);
+            println!("Recent files count: {}", db.recent_files.len());
+            println!("Windows count: {}", db.windows.len());
+            println!();
+        }
+    }
+}
+
+/// Persist the calling window's tabs and recent files.
+#[command]
+pub async fn save_editor_state(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    state: WindowState,
+) -> Result<(), String> {
     #[cfg(dev)]
     {
-        println!("==========Successfully saved editor state==========");
-        println!("Recent files count: {}", state.recent_files.len());
-        println!("Tabs count: {}", state.session.tabs.len());
-        println!("Schema version: {:?}", state.schema_version);
-        println!();
+        println!("Attempting to save editor state");
     }
 
+    let label = window.label();
+    let save = WindowSave::new(&app, &window, state).await;
+    let saved_at = save.now;
+
+    update_session(&app, |db| {
+        save.apply(db, label);
+        Ok(())
+    })
+    .await?;
+
+    // The session file now holds everything the journal was protecting up
+    // to the save.
+    clear_journal(&app, label, saved_at).await;
+
     Ok(())
 }
+
+/// Drop the journal entries of window `label` up to `saved_at` once the
+/// session file holds its tabs as of then.
+pub(crate) async fn clear_journal(app: &tauri::AppHandle, label: &str, saved_at: u64) {
+    let journal = app.state::<Journal>().inner().clone();
+    let label = label.to_string();
+
+    match tokio::task::spawn_blocking(move || journal.clear_window(&label, saved_at)).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => eprintln!("Warning: could not clear the tab journal: {}", e),
+        Err(e) => eprintln!("Warning: journal task panicked: {}", e),
+    }
+}
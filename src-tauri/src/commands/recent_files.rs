@@ -0,0 +1,58 @@
+use tauri::{AppHandle, command};
+
+use crate::commands::migration::{self, LocalFile, RankedFile};
+use crate::commands::settings::read_settings;
+
+fn now() -> u64 {
+    chrono::Utc::now().timestamp_millis() as u64
+}
+
+/// Count an open of `path` in a tab towards its frecency. Returns the file
+/// as now recorded.
+#[command]
+pub async fn record_file_opened(app: AppHandle, path: String) -> Result<LocalFile, String> {
+    let max_recent_files = read_settings(&app).await?.max_recent_files;
+    let now = now();
+
+    migration::update_session(&app, |db| {
+        let file = db.record_open(path, now).clone();
+        db.prune_recent_files(max_recent_files, now);
+        Ok(file)
+    })
+    .await
+}
+
+/// The recent files, pinned ones first and the rest by frecency, at most
+/// `limit` of them.
+#[command]
+pub async fn list_recent_files(
+    app: AppHandle,
+    limit: Option<usize>,
+) -> Result<Vec<RankedFile>, String> {
+    migration::inspect_session(&app, |db| {
+        let mut ranked = db.ranked_recent_files(now());
+        if let Some(limit) = limit {
+            ranked.truncate(limit);
+        }
+        ranked
+    })
+    .await
+}
+
+/// Pin `path` to the top of the recent files, or unpin it.
+#[command]
+pub async fn set_recent_file_pinned(
+    app: AppHandle,
+    path: String,
+    pinned: bool,
+) -> Result<(), String> {
+    migration::update_session(&app, |db| {
+        let file = db
+            .recent_files
+            .get_mut(&path)
+            .ok_or_else(|| "File is not among the recent files".to_string())?;
+        file.pinned = pinned;
+        Ok(())
+    })
+    .await
+}
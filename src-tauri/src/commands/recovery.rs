@@ -0,0 +1,54 @@
+use tauri::command;
+
+use crate::commands::files::FileVersion;
+use crate::journal::{Journal, JournalEntry, TabChange};
+
+async fn append(journal: Journal, entry: JournalEntry) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || journal.append(&entry))
+        .await
+        .map_err(|e| format!("Journal task panicked: {}", e))?
+        .map_err(|e| format!("Error writing journal: {}", e))
+}
+
+/// Record the current unsaved content of a tab so it survives a crash.
+#[command]
+pub async fn journal_tab_content(
+    window: tauri::WebviewWindow,
+    journal: tauri::State<'_, Journal>,
+    tab_id: String,
+    filename: String,
+    path: Option<String>,
+    content: String,
+    base_version: Option<FileVersion>,
+) -> Result<(), String> {
+    let entry = JournalEntry {
+        ts: chrono::Utc::now().timestamp_millis() as u64,
+        window: window.label().to_string(),
+        tab_id,
+        change: TabChange::Content {
+            filename,
+            path,
+            content,
+            base_version,
+        },
+    };
+
+    append(journal.inner().clone(), entry).await
+}
+
+/// Record that a tab was closed, so its journaled content isn't recovered.
+#[command]
+pub async fn journal_tab_closed(
+    window: tauri::WebviewWindow,
+    journal: tauri::State<'_, Journal>,
+    tab_id: String,
+) -> Result<(), String> {
+    let entry = JournalEntry {
+        ts: chrono::Utc::now().timestamp_millis() as u64,
+        window: window.label().to_string(),
+        tab_id,
+        change: TabChange::Closed,
+    };
+
+    append(journal.inner().clone(), entry).await
+}
@@ -0,0 +1,263 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tauri::{Manager, command};
+
+use crate::commands::migration::get_v2_data_dir;
+use crate::snapshots::RetentionPolicy;
+use crate::{salvage, utils};
+
+/// Smallest and largest editor font size the settings will accept.
+const MIN_FONT_SIZE: u32 = 8;
+const MAX_FONT_SIZE: u32 = 72;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    Light,
+    #[default]
+    Dark,
+}
+
+/// User preferences persisted in `settings.json`, next to `session.json`.
+///
+/// Keys this version doesn't know about are kept in `extra` and written
+/// back untouched, so a newer build's settings survive a downgrade.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Settings {
+    pub theme: Theme,
+    pub font_size: u32,
+    /// Limits for the local version history of saved files.
+    pub history: RetentionPolicy,
+    /// Recent files kept besides the pinned ones; `0` keeps them all.
+    pub max_recent_files: usize,
+    /// Folder the user keeps their notes in. Set when V1 notes are migrated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes_home: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            theme: Theme::default(),
+            font_size: 16,
+            history: RetentionPolicy::default(),
+            max_recent_files: 100,
+            notes_home: None,
+            extra: Map::new(),
+        }
+    }
+}
+
+impl Settings {
+    /// Build settings from an arbitrary JSON object.
+    ///
+    /// Every known key is parsed on its own: a missing or invalid value falls
+    /// back to its default instead of discarding the whole file.
+    fn from_map(mut map: Map<String, Value>) -> Self {
+        let defaults = Settings::default();
+
+        let theme = map
+            .remove("theme")
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or(defaults.theme);
+
+        let font_size = map
+            .remove("fontSize")
+            .and_then(|v| serde_json::from_value::<u32>(v).ok())
+            .map(|size| size.clamp(MIN_FONT_SIZE, MAX_FONT_SIZE))
+            .unwrap_or(defaults.font_size);
+
+        let history = map
+            .remove("history")
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or(defaults.history);
+
+        let max_recent_files = map
+            .remove("maxRecentFiles")
+            .and_then(|v| serde_json::from_value::<usize>(v).ok())
+            .unwrap_or(defaults.max_recent_files);
+
+        let notes_home = map
+            .remove("notesHome")
+            .and_then(|v| serde_json::from_value::<String>(v).ok())
+            .filter(|path| !path.is_empty());
+
+        Settings {
+            theme,
+            font_size,
+            history,
+            max_recent_files,
+            notes_home,
+            extra: map,
+        }
+    }
+
+    fn to_map(&self) -> Map<String, Value> {
+        match serde_json::to_value(self) {
+            Ok(Value::Object(map)) => map,
+            _ => Map::new(),
+        }
+    }
+}
+
+pub(crate) fn get_settings_path(app: &tauri::AppHandle) -> PathBuf {
+    settings_path(&get_v2_data_dir(app))
+}
+
+pub(crate) fn settings_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("settings.json")
+}
+
+/// Parse the contents of a settings file read from `path`. A file that isn't
+/// a JSON object is moved aside before defaults are used, so the next write
+/// doesn't replace what the user had.
+fn parse_settings(path: &Path, raw: &[u8]) -> Settings {
+    match serde_json::from_slice::<Value>(raw) {
+        Ok(Value::Object(map)) => Settings::from_map(map),
+        _ => {
+            match salvage::quarantine(path) {
+                Ok(moved) => eprintln!(
+                    "Error parsing settings file, moved it to {} and using defaults",
+                    moved.display()
+                ),
+                Err(e) => eprintln!(
+                    "Error parsing settings file, using defaults; could not move it aside: {}",
+                    e
+                ),
+            }
+            Settings::default()
+        }
+    }
+}
+
+/// Blocking counterpart of [`read_settings`], for use outside the app.
+pub(crate) fn load_settings_file(path: &Path) -> Result<Settings, String> {
+    match std::fs::read(path) {
+        Ok(raw) => Ok(parse_settings(path, &raw)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Settings::default()),
+        Err(e) => Err(format!("Error reading settings file: {}", e)),
+    }
+}
+
+/// Blocking write of `settings` to `path`, for use outside the app. Take
+/// [`lock_settings_file`] before reading the settings being written.
+pub(crate) fn save_settings_file(path: &Path, settings: &Settings) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Error creating app data dir: {}", e))?;
+    }
+
+    let serialized = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Serialization error: {}", e))?;
+
+    utils::atomic_write(path, serialized).map_err(|e| format!("Error writing settings file: {}", e))
+}
+
+/// Lock `settings.lock` next to the settings file at `path`, blocking until
+/// no other process holds it. The lock is released when the file is dropped.
+///
+/// Held while settings are read, changed and written back, so the app and
+/// the command line can't each write back a copy missing the other's change.
+pub(crate) fn lock_settings_file(path: &Path) -> Result<std::fs::File, String> {
+    let lock_path = path.with_file_name("settings.lock");
+    if let Some(parent) = lock_path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Error creating app data dir: {}", e))?;
+    }
+
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .map_err(|e| format!("Error opening settings lock: {}", e))?;
+    file.lock()
+        .map_err(|e| format!("Error locking settings: {}", e))?;
+
+    Ok(file)
+}
+
+/// Serializes changes to the settings file, so two updates racing each
+/// other can't each write back a copy missing the other's change. Other
+/// processes are kept out by [`lock_settings_file`], taken while this is held.
+#[derive(Default)]
+pub struct SettingsLock(tokio::sync::Mutex<()>);
+
+/// Lock the settings against other tasks and processes until the returned
+/// guards are dropped.
+async fn lock_settings(
+    app: &tauri::AppHandle,
+) -> Result<(tokio::sync::MutexGuard<'_, ()>, std::fs::File), String> {
+    let guard = app.state::<SettingsLock>().inner().0.lock().await;
+    let path = get_settings_path(app);
+    let file = tokio::task::spawn_blocking(move || lock_settings_file(&path))
+        .await
+        .map_err(|e| format!("Settings lock task panicked: {}", e))??;
+
+    Ok((guard, file))
+}
+
+/// Read the settings file, returning defaults when it is missing or corrupt.
+/// Any other error reading it is returned, so the file isn't replaced.
+pub(crate) async fn read_settings(app: &tauri::AppHandle) -> Result<Settings, String> {
+    let path = get_settings_path(app);
+
+    match tokio::fs::read(&path).await {
+        Ok(raw) => Ok(parse_settings(&path, &raw)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Settings::default()),
+        Err(e) => Err(format!("Error reading settings file: {}", e)),
+    }
+}
+
+async fn write_settings(app: &tauri::AppHandle, settings: &Settings) -> Result<(), String> {
+    let path = get_settings_path(app);
+
+    tokio::fs::create_dir_all(get_v2_data_dir(app))
+        .await
+        .map_err(|e| format!("Error creating app data dir: {}", e))?;
+
+    let serialized = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Serialization error: {}", e))?;
+
+    utils::atomic_write_async(path, serialized)
+        .await
+        .map_err(|e| format!("Error writing settings file: {}", e))
+}
+
+/// Record `path` as the notes home.
+pub(crate) async fn set_notes_home(app: &tauri::AppHandle, path: String) -> Result<(), String> {
+    let _lock = lock_settings(app).await?;
+    let mut settings = read_settings(app).await?;
+    settings.notes_home = Some(path);
+    write_settings(app, &settings).await
+}
+
+/// Return the persisted settings, or defaults on first run.
+#[command]
+pub async fn get_settings(app: tauri::AppHandle) -> Result<Settings, String> {
+    read_settings(&app).await
+}
+
+/// Merge `changes` into the persisted settings and return the result.
+///
+/// Only the keys present in `changes` are touched, so the frontend can send
+/// e.g. `{ "theme": "light" }` without knowing about every other setting.
+#[command]
+pub async fn update_settings(
+    app: tauri::AppHandle,
+    changes: Map<String, Value>,
+) -> Result<Settings, String> {
+    let _lock = lock_settings(&app).await?;
+    let mut map = read_settings(&app).await?.to_map();
+    map.extend(changes);
+
+    let settings = Settings::from_map(map);
+    write_settings(&app, &settings).await?;
+
+    Ok(settings)
+}
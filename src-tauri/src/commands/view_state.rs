@@ -0,0 +1,24 @@
+use tauri::{AppHandle, command};
+
+use crate::commands::migration::{self, ViewState};
+
+/// Remember where the user was in `path`, normally when its tab is closed,
+/// so reopening the file restores it.
+#[command]
+pub async fn remember_view_state(
+    app: AppHandle,
+    path: String,
+    view: ViewState,
+) -> Result<(), String> {
+    migration::update_session(&app, |db| {
+        db.remember_view(path, view);
+        Ok(())
+    })
+    .await
+}
+
+/// The view state last remembered for `path`, if any.
+#[command]
+pub async fn get_view_state(app: AppHandle, path: String) -> Result<Option<ViewState>, String> {
+    migration::inspect_session(&app, |db| db.remembered_view(&path).cloned()).await
+}
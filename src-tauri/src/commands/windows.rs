@@ -0,0 +1,190 @@
+use std::sync::Mutex;
+
+use tauri::{
+    AppHandle, Emitter, Manager, PhysicalPosition, PhysicalSize, Result, WebviewWindow,
+    WebviewWindowBuilder,
+};
+
+use crate::CliFilePaths;
+use crate::cli::{OpenRequest, OpenTarget};
+use crate::commands::migration::{DatabaseV4, SessionTab, WindowGeometry};
+
+/// Label of the window declared in `tauri.conf.json`.
+pub const MAIN_WINDOW: &str = "main";
+
+/// Label of the window that last had focus. Files opened from a second
+/// instance go there.
+#[derive(Default)]
+pub struct FocusedWindow(pub Mutex<Option<String>>);
+
+/// A label no open window uses yet.
+fn new_label(app: &AppHandle) -> String {
+    let base = format!("window-{}", chrono::Utc::now().timestamp_millis());
+    let mut label = base.clone();
+    let mut n = 1;
+
+    while app.get_webview_window(&label).is_some() {
+        n += 1;
+        label = format!("{}-{}", base, n);
+    }
+
+    label
+}
+
+/// Open a window configured like the main one, placed at `geometry` when
+/// one was saved for it.
+pub(crate) fn create_window(
+    app: &AppHandle,
+    label: &str,
+    geometry: Option<&WindowGeometry>,
+) -> Result<WebviewWindow> {
+    let mut config = app
+        .config()
+        .app
+        .windows
+        .first()
+        .cloned()
+        .unwrap_or_default();
+    config.label = label.to_string();
+
+    let window = WebviewWindowBuilder::from_config(app, &config)?.build()?;
+
+    if let Some(geometry) = geometry {
+        window.set_position(PhysicalPosition::new(geometry.x, geometry.y))?;
+        window.set_size(PhysicalSize::new(geometry.width, geometry.height))?;
+        if geometry.maximized {
+            window.maximize()?;
+        }
+    }
+
+    Ok(window)
+}
+
+/// Where `window` is on screen, to be saved with its tabs. `None` while it
+/// is minimized, since its position is meaningless then.
+pub(crate) fn geometry_of(window: &WebviewWindow) -> Option<WindowGeometry> {
+    if window.is_minimized().unwrap_or(false) {
+        return None;
+    }
+
+    let position = window.outer_position().ok()?;
+    let size = window.inner_size().ok()?;
+
+    Some(WindowGeometry {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+        maximized: window.is_maximized().unwrap_or(false),
+    })
+}
+
+/// Reopen the windows saved in `db` that aren't open yet. The main window's
+/// geometry is restored by the window-state plugin.
+pub(crate) fn restore_windows(app: &AppHandle, db: &DatabaseV4) {
+    for saved in &db.windows {
+        if app.get_webview_window(&saved.label).is_some() {
+            continue;
+        }
+
+        if let Err(e) = create_window(app, &saved.label, saved.geometry.as_ref()) {
+            eprintln!("Error restoring window {}: {}", saved.label, e);
+        }
+    }
+}
+
+/// Open a new window for `requests`, which it picks up with
+/// `take_cli_file_paths` once loaded. Returns the new window's label.
+pub(crate) fn open_window(app: &AppHandle, requests: Vec<OpenRequest>) -> Result<String> {
+    let label = new_label(app);
+
+    if !requests.is_empty() {
+        app.state::<CliFilePaths>()
+            .0
+            .lock()
+            .unwrap()
+            .insert(label.clone(), requests);
+    }
+
+    create_window(app, &label, None)?;
+    Ok(label)
+}
+
+/// The window that last had focus, falling back to the main window or any
+/// window at all.
+fn focused_window(app: &AppHandle) -> Option<WebviewWindow> {
+    let focused = app.state::<FocusedWindow>().0.lock().unwrap().clone();
+
+    focused
+        .and_then(|label| app.get_webview_window(&label))
+        .or_else(|| app.get_webview_window(MAIN_WINDOW))
+        .or_else(|| app.webview_windows().into_values().next())
+}
+
+/// Show what a second instance asked to open: in a new window for
+/// `--new-window`, otherwise in the window that last had focus.
+pub(crate) fn forward_requests(app: &AppHandle, requests: Vec<OpenRequest>, new_window: bool) {
+    let existing = if new_window {
+        None
+    } else {
+        focused_window(app)
+    };
+
+    let window = match existing {
+        Some(window) => {
+            if !requests.is_empty() {
+                let _ = app.emit_to(window.label(), "open-files", &requests);
+            }
+            Some(window)
+        }
+        None => match open_window(app, requests) {
+            Ok(label) => app.get_webview_window(&label),
+            Err(e) => {
+                eprintln!("Error opening a new window: {}", e);
+                None
+            }
+        },
+    };
+
+    // Bring the window to the foreground.
+    if let Some(window) = window {
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+/// Open an empty window. Returns its label.
+#[tauri::command]
+pub async fn new_window(app: AppHandle) -> Result<String> {
+    open_window(&app, Vec::new())
+}
+
+/// Hand a tab over to window `target`, or to a new window when `target` is
+/// `None` or no longer open. The receiving window gets it as an `open-files`
+/// request; the calling window drops its own copy once this returns.
+/// Returns the label of the receiving window.
+#[tauri::command]
+pub async fn move_tab_to_window(
+    app: AppHandle,
+    tab: SessionTab,
+    read_only: bool,
+    wait_id: Option<String>,
+    target: Option<String>,
+) -> Result<String> {
+    let existing = target.and_then(|label| app.get_webview_window(&label));
+    let request = OpenRequest {
+        target: OpenTarget::Tab { tab },
+        read_only,
+        new_window: existing.is_none(),
+        wait_id,
+    };
+
+    match existing {
+        Some(window) => {
+            app.emit_to(window.label(), "open-files", vec![request])?;
+            let _ = window.set_focus();
+            Ok(window.label().to_string())
+        }
+        None => open_window(&app, vec![request]),
+    }
+}
@@ -0,0 +1,262 @@
+use serde::Serialize;
+use tauri::{AppHandle, Manager, WebviewWindow};
+
+use crate::commands::migration::{
+    self, DatabaseV4, EditorSession, WindowSave, WindowState, Workspace,
+};
+
+/// A workspace as listed for the user.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceSummary {
+    pub id: String,
+    pub name: String,
+    pub tab_count: usize,
+    /// Label of the window showing the workspace, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window: Option<String>,
+}
+
+/// The tabs of `workspace`: those its window last saved while a window
+/// shows it, its parked tabs otherwise.
+fn session_of<'a>(db: &'a DatabaseV4, workspace: &'a Workspace) -> Option<&'a EditorSession> {
+    match db.shown_in(&workspace.id) {
+        Some(label) => db.window(label).map(|w| &w.session),
+        None => workspace.parked.as_ref(),
+    }
+}
+
+fn summary(db: &DatabaseV4, workspace: &Workspace) -> WorkspaceSummary {
+    WorkspaceSummary {
+        id: workspace.id.clone(),
+        name: workspace.name.clone(),
+        tab_count: session_of(db, workspace).map_or(0, |s| s.tabs.len()),
+        window: db.shown_in(&workspace.id).map(str::to_string),
+    }
+}
+
+fn find<'a>(db: &'a DatabaseV4, id: &str) -> Result<&'a Workspace, String> {
+    db.workspace(id)
+        .ok_or_else(|| "Workspace not found".to_string())
+}
+
+/// `name` without surrounding whitespace, if it isn't empty and no workspace
+/// other than `renaming` uses it.
+fn check_name(db: &DatabaseV4, name: &str, renaming: Option<&str>) -> Result<String, String> {
+    let name = name.trim();
+
+    if name.is_empty() {
+        return Err("Workspace name cannot be empty".to_string());
+    }
+
+    if db
+        .workspaces
+        .iter()
+        .any(|w| w.name == name && Some(w.id.as_str()) != renaming)
+    {
+        return Err(format!("A workspace named \"{}\" already exists", name));
+    }
+
+    Ok(name.to_string())
+}
+
+/// Every workspace, in the order they were created.
+#[tauri::command]
+pub async fn list_workspaces(app: AppHandle) -> Result<Vec<WorkspaceSummary>, String> {
+    migration::inspect_session(&app, |db| {
+        db.workspaces.iter().map(|w| summary(db, w)).collect()
+    })
+    .await
+}
+
+/// Add an empty workspace. Windows keep showing what they showed.
+#[tauri::command]
+pub async fn create_workspace(app: AppHandle, name: String) -> Result<WorkspaceSummary, String> {
+    migration::update_session(&app, |db| {
+        let name = check_name(db, &name, None)?;
+        let id = db.add_workspace(name, Some(EditorSession::default()));
+        Ok(summary(db, find(db, &id)?))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn rename_workspace(app: AppHandle, id: String, name: String) -> Result<(), String> {
+    migration::update_session(&app, |db| {
+        find(db, &id)?;
+        let name = check_name(db, &name, Some(&id))?;
+
+        if let Some(workspace) = db.workspace_mut(&id) {
+            workspace.name = name;
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// Copy workspace `id` and its tabs, unsaved content included, into a new
+/// workspace no window shows. It is named `name`, or "<name> copy" when
+/// `None`. A workspace shown in a window is copied as that window last
+/// saved it.
+#[tauri::command]
+pub async fn duplicate_workspace(
+    app: AppHandle,
+    id: String,
+    name: Option<String>,
+) -> Result<WorkspaceSummary, String> {
+    migration::update_session(&app, |db| {
+        let original = find(db, &id)?;
+        let name = match name {
+            Some(name) => check_name(db, &name, None)?,
+            None => (1..)
+                .map(|n| match n {
+                    1 => format!("{} copy", original.name),
+                    n => format!("{} copy {}", original.name, n),
+                })
+                .find(|name| db.workspaces.iter().all(|w| &w.name != name))
+                .unwrap_or_default(),
+        };
+
+        // Tabs get new ids so both workspaces can be open side by side.
+        let mut session = session_of(db, original).cloned().unwrap_or_default();
+        for tab in &mut session.tabs {
+            let new_id = uuid::Uuid::new_v4().to_string();
+            if session.current_tab_id.as_ref() == Some(&tab.id) {
+                session.current_tab_id = Some(new_id.clone());
+            }
+            tab.id = new_id;
+        }
+
+        let copy = db.add_workspace(name, Some(session));
+        Ok(summary(db, find(db, &copy)?))
+    })
+    .await
+}
+
+/// Delete workspace `id` and its tabs. A workspace shown in a window can't
+/// be deleted until that window switches away from it, and the last
+/// workspace can't be deleted at all.
+#[tauri::command]
+pub async fn delete_workspace(app: AppHandle, id: String) -> Result<(), String> {
+    migration::update_session(&app, |db| {
+        find(db, &id)?;
+
+        if db.shown_in(&id).is_some() {
+            return Err(
+                "The workspace is open in a window; switch that window to another workspace first"
+                    .to_string(),
+            );
+        }
+        if db.workspaces.len() == 1 {
+            return Err("The last workspace cannot be deleted".to_string());
+        }
+
+        db.workspaces.retain(|w| w.id != id);
+        Ok(())
+    })
+    .await
+}
+
+/// Show workspace `id` in the calling window. The window's current tabs,
+/// `state`, are saved and parked in its old workspace in the same write, so
+/// nothing is parked unless it was saved.
+///
+/// Returns the tabs to show, or `None` when another window already shows
+/// the workspace; that window is focused instead.
+#[tauri::command]
+pub async fn switch_workspace(
+    app: AppHandle,
+    window: WebviewWindow,
+    id: String,
+    state: WindowState,
+) -> Result<Option<EditorSession>, String> {
+    let label = window.label();
+
+    let shown_in = migration::inspect_session(&app, |db| {
+        db.shown_in(&id)
+            .filter(|shown_in| *shown_in != label)
+            .map(str::to_string)
+    })
+    .await?;
+
+    if let Some(other) = shown_in.and_then(|l| app.get_webview_window(&l)) {
+        let _ = other.unminimize();
+        let _ = other.set_focus();
+        return Ok(None);
+    }
+
+    let save = WindowSave::new(&app, &window, state).await;
+    let saved_at = save.now;
+
+    let session = migration::update_session(&app, |db| {
+        find(db, &id)?;
+
+        if db.shown_in(&id).is_some_and(|shown_in| shown_in != label) {
+            return Err("The workspace is open in another window".to_string());
+        }
+
+        save.apply(db, label);
+
+        let current = db.window_mut(label);
+        if current.workspace == id {
+            return Ok(current.session.clone());
+        }
+
+        let previous = std::mem::replace(&mut current.workspace, id.clone());
+        let tabs = std::mem::take(&mut current.session);
+
+        let session = db
+            .workspace_mut(&id)
+            .and_then(|w| w.parked.take())
+            .unwrap_or_default();
+        db.window_mut(label).session = session.clone();
+
+        if let Some(previous) = db.workspace_mut(&previous) {
+            previous.parked = Some(tabs);
+        }
+
+        Ok(session)
+    })
+    .await?;
+
+    // Entries up to the save belong to the tabs that were just parked.
+    migration::clear_journal(&app, label, saved_at).await;
+
+    Ok(Some(session))
+}
+
+/// Drop the calling window from the session because it is closing while
+/// other windows stay open. Its tabs, `state`, are saved and parked in its
+/// workspace in the same write; a workspace left without tabs is deleted.
+#[tauri::command]
+pub async fn detach_window(
+    app: AppHandle,
+    window: WebviewWindow,
+    state: WindowState,
+) -> Result<(), String> {
+    let label = window.label();
+    let save = WindowSave::new(&app, &window, state).await;
+    let saved_at = save.now;
+
+    migration::update_session(&app, |db| {
+        save.apply(db, label);
+
+        let Some(index) = db.windows.iter().position(|w| w.label == label) else {
+            return Ok(());
+        };
+        let detached = db.windows.remove(index);
+
+        if detached.session.tabs.is_empty() && db.workspaces.len() > 1 {
+            db.workspaces.retain(|w| w.id != detached.workspace);
+        } else if let Some(workspace) = db.workspace_mut(&detached.workspace) {
+            workspace.parked = Some(detached.session);
+        }
+
+        Ok(())
+    })
+    .await?;
+
+    migration::clear_journal(&app, label, saved_at).await;
+
+    Ok(())
+}
@@ -0,0 +1,315 @@
+use std::io::{Error, ErrorKind};
+
+use encoding_rs::WINDOWS_1252;
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+/// How many leading bytes the BOM-less UTF-16 heuristic looks at.
+const SNIFF_LEN: usize = 4096;
+
+/// Text encodings the editor can read and write back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextEncoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Windows1252,
+    Latin1,
+}
+
+impl TextEncoding {
+    /// Label reported to the frontend in `FileInfo.encoding`.
+    pub fn label(self) -> &'static str {
+        match self {
+            TextEncoding::Utf8 => "UTF-8",
+            TextEncoding::Utf16Le => "UTF-16LE",
+            TextEncoding::Utf16Be => "UTF-16BE",
+            TextEncoding::Windows1252 => "windows-1252",
+            TextEncoding::Latin1 => "ISO-8859-1",
+        }
+    }
+
+    /// Parse a label sent by the frontend. Common aliases are accepted and
+    /// matching is case-insensitive.
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label.trim().to_ascii_lowercase().as_str() {
+            "utf-8" | "utf8" => Some(TextEncoding::Utf8),
+            "utf-16le" | "utf16le" | "utf-16" | "utf16" => Some(TextEncoding::Utf16Le),
+            "utf-16be" | "utf16be" => Some(TextEncoding::Utf16Be),
+            "windows-1252" | "cp1252" => Some(TextEncoding::Windows1252),
+            "iso-8859-1" | "latin1" | "latin-1" => Some(TextEncoding::Latin1),
+            _ => None,
+        }
+    }
+
+    fn bom(self) -> &'static [u8] {
+        match self {
+            TextEncoding::Utf8 => UTF8_BOM,
+            TextEncoding::Utf16Le => UTF16LE_BOM,
+            TextEncoding::Utf16Be => UTF16BE_BOM,
+            TextEncoding::Windows1252 | TextEncoding::Latin1 => &[],
+        }
+    }
+
+    /// Whether a BOM is written when the caller doesn't say otherwise.
+    /// UTF-16 without a BOM is hard for other programs to detect.
+    pub fn default_bom(self) -> bool {
+        matches!(self, TextEncoding::Utf16Le | TextEncoding::Utf16Be)
+    }
+}
+
+/// Result of decoding raw file bytes.
+#[derive(Debug)]
+pub struct DecodedText {
+    pub content: String,
+    pub encoding: TextEncoding,
+    pub has_bom: bool,
+}
+
+/// Detect the encoding of `bytes` and decode them into a `String`.
+///
+/// Detection order: BOM, BOM-less UTF-16 (by the distribution of zero
+/// bytes), valid UTF-8, then the single-byte Windows-1252 / ISO-8859-1
+/// fallback. Valid UTF-8 is accepted even with NUL bytes in it; only files
+/// that are neither UTF-8 nor UTF-16 and contain NUL bytes are rejected as
+/// binary.
+pub fn decode(bytes: &[u8]) -> Result<DecodedText, Error> {
+    if let Some(encoding) = sniff_bom(bytes) {
+        let body = &bytes[encoding.bom().len()..];
+        return Ok(DecodedText {
+            content: decode_as(body, encoding)?,
+            encoding,
+            has_bom: true,
+        });
+    }
+
+    // ASCII text in UTF-16 is valid UTF-8 too, so UTF-16 is tried first.
+    let encoding = if let Some(encoding) = sniff_utf16(bytes) {
+        encoding
+    } else if std::str::from_utf8(bytes).is_ok() {
+        TextEncoding::Utf8
+    } else if bytes.contains(&0) {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "File appears to be binary and can't be opened as text.",
+        ));
+    } else if bytes.iter().any(|b| is_undefined_in_windows_1252(*b)) {
+        TextEncoding::Latin1
+    } else {
+        TextEncoding::Windows1252
+    };
+
+    Ok(DecodedText {
+        content: decode_as(bytes, encoding)?,
+        encoding,
+        has_bom: false,
+    })
+}
+
+/// Encode `content` for writing to disk, optionally prefixed with a BOM.
+///
+/// Fails instead of silently substituting characters that the target
+/// encoding can't represent.
+pub fn encode(content: &str, encoding: TextEncoding, with_bom: bool) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::with_capacity(content.len() + 3);
+    if with_bom {
+        out.extend_from_slice(encoding.bom());
+    }
+
+    match encoding {
+        TextEncoding::Utf8 => out.extend_from_slice(content.as_bytes()),
+        TextEncoding::Utf16Le => content
+            .encode_utf16()
+            .for_each(|unit| out.extend_from_slice(&unit.to_le_bytes())),
+        TextEncoding::Utf16Be => content
+            .encode_utf16()
+            .for_each(|unit| out.extend_from_slice(&unit.to_be_bytes())),
+        TextEncoding::Windows1252 => {
+            let (encoded, _, had_errors) = WINDOWS_1252.encode(content);
+            if had_errors {
+                return Err(unrepresentable(encoding));
+            }
+            out.extend_from_slice(&encoded);
+        }
+        TextEncoding::Latin1 => {
+            for c in content.chars() {
+                let code = u32::from(c);
+                if code > 0xFF {
+                    return Err(unrepresentable(encoding));
+                }
+                out.push(code as u8);
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+fn sniff_bom(bytes: &[u8]) -> Option<TextEncoding> {
+    if bytes.starts_with(UTF8_BOM) {
+        Some(TextEncoding::Utf8)
+    } else if bytes.starts_with(UTF16LE_BOM) {
+        Some(TextEncoding::Utf16Le)
+    } else if bytes.starts_with(UTF16BE_BOM) {
+        Some(TextEncoding::Utf16Be)
+    } else {
+        None
+    }
+}
+
+/// Guess BOM-less UTF-16 from where zero bytes fall.
+///
+/// Mostly-ASCII UTF-16LE text has a zero in almost every odd byte and none in
+/// the even ones (and the reverse for big endian).
+fn sniff_utf16(bytes: &[u8]) -> Option<TextEncoding> {
+    if bytes.len() < 2 || !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+
+    let sample = &bytes[..bytes.len().min(SNIFF_LEN)];
+    let pairs = sample.len() / 2;
+    let (mut even_zeros, mut odd_zeros) = (0usize, 0usize);
+
+    for pair in sample.chunks_exact(2) {
+        if pair[0] == 0 {
+            even_zeros += 1;
+        }
+        if pair[1] == 0 {
+            odd_zeros += 1;
+        }
+    }
+
+    let mostly = |count: usize| count * 10 >= pairs * 3;
+    let rarely = |count: usize| count * 20 <= pairs;
+
+    let encoding = if mostly(odd_zeros) && rarely(even_zeros) {
+        TextEncoding::Utf16Le
+    } else if mostly(even_zeros) && rarely(odd_zeros) {
+        TextEncoding::Utf16Be
+    } else {
+        return None;
+    };
+
+    // Only accept the guess if the whole file is well-formed UTF-16.
+    decode_as(bytes, encoding).ok().map(|_| encoding)
+}
+
+/// Bytes that have no mapping in Windows-1252. Their presence means the text
+/// is more likely ISO-8859-1 with C1 control characters.
+fn is_undefined_in_windows_1252(byte: u8) -> bool {
+    matches!(byte, 0x81 | 0x8D | 0x8F | 0x90 | 0x9D)
+}
+
+fn decode_as(bytes: &[u8], encoding: TextEncoding) -> Result<String, Error> {
+    match encoding {
+        TextEncoding::Utf8 => String::from_utf8(bytes.to_vec())
+            .map_err(|_| Error::new(ErrorKind::InvalidData, "File is not valid UTF-8.")),
+        TextEncoding::Utf16Le | TextEncoding::Utf16Be => {
+            if !bytes.len().is_multiple_of(2) {
+                return Err(malformed(encoding));
+            }
+            let units: Vec<u16> = bytes
+                .chunks_exact(2)
+                .map(|pair| match encoding {
+                    TextEncoding::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+                    _ => u16::from_be_bytes([pair[0], pair[1]]),
+                })
+                .collect();
+            String::from_utf16(&units).map_err(|_| malformed(encoding))
+        }
+        TextEncoding::Windows1252 => Ok(WINDOWS_1252
+            .decode_without_bom_handling(bytes)
+            .0
+            .into_owned()),
+        TextEncoding::Latin1 => Ok(bytes.iter().map(|&b| char::from(b)).collect()),
+    }
+}
+
+fn malformed(encoding: TextEncoding) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("File is not valid {}.", encoding.label()),
+    )
+}
+
+fn unrepresentable(encoding: TextEncoding) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!(
+            "The document contains characters that can't be saved as {}.",
+            encoding.label()
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "héllo € wörld\r\nline";
+
+    #[test]
+    fn bom_decides_the_encoding() {
+        for encoding in [
+            TextEncoding::Utf8,
+            TextEncoding::Utf16Le,
+            TextEncoding::Utf16Be,
+        ] {
+            let decoded = decode(&encode(TEXT, encoding, true).unwrap()).unwrap();
+
+            assert_eq!(decoded.encoding, encoding);
+            assert!(decoded.has_bom);
+            assert_eq!(decoded.content, TEXT);
+        }
+    }
+
+    #[test]
+    fn utf16_without_bom_is_detected() {
+        for encoding in [TextEncoding::Utf16Le, TextEncoding::Utf16Be] {
+            let decoded = decode(&encode(TEXT, encoding, false).unwrap()).unwrap();
+
+            assert_eq!(decoded.encoding, encoding);
+            assert!(!decoded.has_bom);
+            assert_eq!(decoded.content, TEXT);
+        }
+    }
+
+    #[test]
+    fn valid_utf8_is_read_as_utf8() {
+        let decoded = decode(TEXT.as_bytes()).unwrap();
+
+        assert_eq!(decoded.encoding, TextEncoding::Utf8);
+        assert!(!decoded.has_bom);
+        assert_eq!(decoded.content, TEXT);
+    }
+
+    #[test]
+    fn other_bytes_fall_back_to_single_byte_encodings() {
+        let decoded = decode(&encode(TEXT, TextEncoding::Windows1252, false).unwrap()).unwrap();
+        assert_eq!(decoded.encoding, TextEncoding::Windows1252);
+        assert_eq!(decoded.content, TEXT);
+
+        // 0x81 has no Windows-1252 mapping, so it must be a C1 control.
+        let decoded = decode(&[b'h', 0x81, 0xE9]).unwrap();
+        assert_eq!(decoded.encoding, TextEncoding::Latin1);
+        assert_eq!(decoded.content, "h\u{81}é");
+    }
+
+    #[test]
+    fn nul_bytes_are_text_only_in_utf8() {
+        let decoded = decode(b"a\0b\nc").unwrap();
+        assert_eq!(decoded.encoding, TextEncoding::Utf8);
+        assert_eq!(decoded.content, "a\0b\nc");
+
+        let err = decode(&[0xFF, b'a', 0, b'b', 0, 0, 0x89]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn unrepresentable_characters_are_refused() {
+        assert!(encode("€", TextEncoding::Latin1, false).is_err());
+        assert!(encode("漢", TextEncoding::Windows1252, false).is_err());
+    }
+}
@@ -0,0 +1,88 @@
+//! Files one process of the app leaves for another, such as piped stdin
+//! spooled for the instance that will show it.
+//!
+//! They live in a per-user folder only the user can access and get random
+//! names, so other local users can neither read them nor plant their own
+//! in their place. Only the name travels on the command line.
+
+use std::fs::OpenOptions;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+use crate::headless::IDENTIFIER;
+
+fn invalid(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, message)
+}
+
+/// The handoff folder, created readable by the user alone. A symlink in its
+/// place is refused.
+fn dir() -> std::io::Result<PathBuf> {
+    let base = dirs::runtime_dir()
+        .or_else(dirs::cache_dir)
+        .ok_or_else(|| std::io::Error::other("Could not resolve a per-user folder"))?;
+    let dir = base.join(IDENTIFIER).join("handoff");
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(&dir)?;
+
+        let metadata = std::fs::symlink_metadata(&dir)?;
+        if !metadata.is_dir() {
+            return Err(invalid("The handoff folder is not a folder"));
+        }
+        if metadata.permissions().mode() & 0o077 != 0 {
+            std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700))?;
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        std::fs::create_dir_all(&dir)?;
+        if !std::fs::symlink_metadata(&dir)?.is_dir() {
+            return Err(invalid("The handoff folder is not a folder"));
+        }
+    }
+
+    Ok(dir)
+}
+
+/// Store `contents` under a new random name and return the name.
+pub fn write(contents: &[u8]) -> std::io::Result<String> {
+    let name = uuid::Uuid::new_v4().to_string();
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut file = options.open(dir()?.join(&name))?;
+    file.write_all(contents)?;
+
+    Ok(name)
+}
+
+/// Read and delete the file left under `name`. Only names [`write`] could
+/// have produced are accepted.
+pub fn take(name: &str) -> std::io::Result<Vec<u8>> {
+    if uuid::Uuid::try_parse(name).is_err() {
+        return Err(invalid("Invalid handoff name"));
+    }
+
+    let path = dir()?.join(name);
+    if std::fs::symlink_metadata(&path)?.file_type().is_symlink() {
+        return Err(invalid("The handoff file is a symlink"));
+    }
+
+    let mut bytes = Vec::new();
+    let read = std::fs::File::open(&path).and_then(|mut file| file.read_to_end(&mut bytes));
+    let _ = std::fs::remove_file(&path);
+    read?;
+
+    Ok(bytes)
+}
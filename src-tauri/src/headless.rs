@@ -0,0 +1,295 @@
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::json;
+
+use crate::buffers;
+use crate::commands::files::convert_line_ending_bytes;
+use crate::commands::migration::{
+    self, BUFFERS_DIR, DEFAULT_NOTES_FOLDER, DatabaseV4, JOURNAL_FILE, SESSION_FILE,
+};
+use crate::commands::settings::{
+    load_settings_file, lock_settings_file, save_settings_file, settings_path,
+};
+use crate::journal::{self, Journal};
+use crate::line_ending::LineEnding;
+use crate::utils;
+use crate::v1_migration::{self, MigrationPaths};
+
+/// Bundle identifier from `tauri.conf.json`. Tauri names the app's data
+/// dirs after it, so the two must stay in sync.
+pub(crate) const IDENTIFIER: &str = "com.taking-notes.app";
+
+const USAGE: &str = "usage: taking-notes recent\n       \
+taking-notes session export\n       \
+taking-notes migrate [--dry-run] [--destination DIR]\n       \
+taking-notes convert --eol lf|crlf FILE...";
+
+/// Run `args` (without the executable) as a headless subcommand.
+///
+/// Returns the exit code, or `None` when `args` isn't a subcommand and the
+/// editor should start as usual. Results are printed to stdout as JSON;
+/// failures print `{"error": "..."}` and exit with 1, usage errors with 2.
+/// To open a file named like a subcommand, pass it as `./recent`.
+pub fn run(args: &[String]) -> Option<i32> {
+    let (command, rest) = args.split_first()?;
+    if !SUBCOMMANDS.contains(&command.as_str()) {
+        return None;
+    }
+
+    #[cfg(windows)]
+    attach_console();
+
+    let result = match command.as_str() {
+        "recent" => no_args(rest).and_then(|()| recent()),
+        "session" => match rest {
+            [sub] if sub == "export" => session_export(),
+            _ => Err(Failure::Usage("session needs a subcommand: export".into())),
+        },
+        "migrate" => migrate(rest),
+        "convert" => convert(rest),
+        _ => return None,
+    };
+
+    Some(match result {
+        Ok(value) => print_value(&value, 0),
+        Err(Failure::Usage(message)) => {
+            eprintln!("{}", USAGE);
+            print_error(&message, 2)
+        }
+        Err(Failure::Error(message)) => print_error(&message, 1),
+        Err(Failure::Partial(value)) => print_value(&value, 1),
+    })
+}
+
+const SUBCOMMANDS: &[&str] = &["recent", "session", "migrate", "convert"];
+
+/// Release builds on Windows are GUI programs without a console, so anything
+/// printed would be lost. Use the console of the shell that started us.
+#[cfg(windows)]
+fn attach_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+
+    // Fails when output is redirected or there is no parent console; the
+    // standard handles are fine as they are then.
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+enum Failure {
+    Usage(String),
+    Error(String),
+    /// Some of the work failed; the value reports what happened to each part.
+    Partial(serde_json::Value),
+}
+
+impl From<String> for Failure {
+    fn from(message: String) -> Self {
+        Failure::Error(message)
+    }
+}
+
+fn print_value(value: &serde_json::Value, code: i32) -> i32 {
+    match serde_json::to_string_pretty(value) {
+        Ok(out) => {
+            println!("{}", out);
+            code
+        }
+        Err(e) => print_error(&format!("Serialization error: {}", e), 1),
+    }
+}
+
+fn print_error(message: &str, code: i32) -> i32 {
+    println!("{}", json!({ "error": message }));
+    code
+}
+
+fn no_args(rest: &[String]) -> Result<(), Failure> {
+    match rest.first() {
+        Some(arg) => Err(Failure::Usage(format!("Unexpected argument {}", arg))),
+        None => Ok(()),
+    }
+}
+
+/// Same as the app's `app_data_dir`, where the V2 session lives.
+fn data_dir() -> Result<PathBuf, String> {
+    dirs::data_dir()
+        .map(|dir| dir.join(IDENTIFIER))
+        .ok_or_else(|| "Could not resolve the app data dir".to_string())
+}
+
+/// Same as the app's `app_local_data_dir`, where V1 kept its notes.
+fn local_data_dir() -> Result<PathBuf, String> {
+    dirs::data_local_dir()
+        .map(|dir| dir.join(IDENTIFIER))
+        .ok_or_else(|| "Could not resolve the app local data dir".to_string())
+}
+
+/// The saved session with unsaved edits from their buffers and the journal
+/// applied, as the editor would load it. Nothing is written back.
+fn load_session(data_dir: &Path) -> Result<DatabaseV4, String> {
+    let session_path = data_dir.join(SESSION_FILE);
+
+    let (mut db, session_modified) = match std::fs::metadata(&session_path) {
+        Err(_) => (DatabaseV4::default(), 0),
+        Ok(metadata) => {
+            let bytes = std::fs::read(&session_path)
+                .map_err(|e| format!("Failed reading manager file: {}", e))?;
+            let (db, _) = migration::decode_session(&String::from_utf8_lossy(&bytes))
+                .map_err(|e| format!("Error parsing V2 database: {}", e))?;
+            (db, utils::modified_millis(&metadata))
+        }
+    };
+
+    buffers::load(&data_dir.join(BUFFERS_DIR), &mut db);
+
+    let entries = Journal::new(data_dir.join(JOURNAL_FILE)).read_entries();
+    journal::replay(&mut db, entries, session_modified);
+
+    Ok(db)
+}
+
+/// Recently opened files, ranked as the editor lists them.
+fn recent() -> Result<serde_json::Value, Failure> {
+    let db = load_session(&data_dir()?)?;
+
+    to_value(&db.ranked_recent_files(chrono::Utc::now().timestamp_millis() as u64))
+}
+
+fn session_export() -> Result<serde_json::Value, Failure> {
+    to_value(&load_session(&data_dir()?)?)
+}
+
+/// Plan the V1 to V2 migration, or run it unless `--dry-run` is given.
+fn migrate(rest: &[String]) -> Result<serde_json::Value, Failure> {
+    let mut dry_run = false;
+    let mut destination = None;
+    let mut rest = rest.iter();
+
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--dry-run" => dry_run = true,
+            "--destination" => {
+                let dir = rest
+                    .next()
+                    .ok_or_else(|| Failure::Usage("--destination needs a folder".into()))?;
+                let cwd = std::env::current_dir().unwrap_or_default();
+                destination = Some(cwd.join(dir).to_string_lossy().to_string());
+            }
+            other => return Err(Failure::Usage(format!("Unexpected argument {}", other))),
+        }
+    }
+
+    let data_dir = data_dir()?;
+    let settings_path = settings_path(&data_dir);
+    let notes_home = load_settings_file(&settings_path)?
+        .notes_home
+        .map(PathBuf::from);
+
+    let destination = v1_migration::choose_destination(destination, notes_home.as_deref(), || {
+        dirs::document_dir()
+            .map(|dir| dir.join(DEFAULT_NOTES_FOLDER))
+            .ok_or_else(|| "Could not resolve the documents dir".to_string())
+    })?;
+    let paths = MigrationPaths::new(local_data_dir()?, data_dir, destination, notes_home);
+
+    if dry_run {
+        return to_value(&v1_migration::plan(&paths)?);
+    }
+
+    // Migrating again would replace the session, tabs and workspaces
+    // included, and write behind a running app.
+    if paths.v2_manager.exists() {
+        return Err(Failure::Error(
+            "Notes were already migrated. Open the app to retry the notes that couldn't be copied then".into(),
+        ));
+    }
+
+    let report = v1_migration::migrate(&paths, |progress| {
+        eprintln!(
+            "[{}/{}] {}",
+            progress.current, progress.total, progress.title
+        );
+    })?;
+
+    // Same as the app: the migration folder becomes the notes home. The
+    // settings are read again under the lock the app also takes.
+    let _lock = lock_settings_file(&settings_path)?;
+    let mut settings = load_settings_file(&settings_path)?;
+    settings.notes_home = Some(report.destination.clone());
+    save_settings_file(&settings_path, &settings)?;
+
+    to_value(&report)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConvertedFile {
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    changed_lines: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Convert each file's line endings in place. A file that fails is
+/// reported and the rest are still converted.
+fn convert(rest: &[String]) -> Result<serde_json::Value, Failure> {
+    let (target, files) = match rest {
+        [flag, eol, files @ ..] if flag == "--eol" && !files.is_empty() => {
+            match eol.to_ascii_lowercase().as_str() {
+                "lf" => (LineEnding::Lf, files),
+                "crlf" => (LineEnding::Crlf, files),
+                _ => return Err(Failure::Usage(format!("Unknown line ending {}", eol))),
+            }
+        }
+        _ => {
+            return Err(Failure::Usage(
+                "convert needs --eol lf|crlf and files".into(),
+            ));
+        }
+    };
+
+    let results: Vec<ConvertedFile> = files
+        .iter()
+        .map(|path| {
+            let converted = std::fs::read(path).and_then(|bytes| {
+                let (converted, changed_lines) = convert_line_ending_bytes(&bytes, target)?;
+                if let Some(bytes) = converted {
+                    utils::atomic_write(path, bytes)?;
+                }
+                Ok(changed_lines)
+            });
+
+            match converted {
+                Ok(changed_lines) => ConvertedFile {
+                    path: path.clone(),
+                    changed_lines: Some(changed_lines),
+                    error: None,
+                },
+                Err(e) => ConvertedFile {
+                    path: path.clone(),
+                    changed_lines: None,
+                    error: Some(e.to_string()),
+                },
+            }
+        })
+        .collect();
+
+    let value = to_value(&results)?;
+    if results.iter().any(|r| r.error.is_some()) {
+        return Err(Failure::Partial(value));
+    }
+
+    Ok(value)
+}
+
+fn to_value<T: Serialize>(value: &T) -> Result<serde_json::Value, Failure> {
+    serde_json::to_value(value).map_err(|e| Failure::Error(format!("Serialization error: {}", e)))
+}
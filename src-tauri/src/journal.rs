@@ -0,0 +1,341 @@
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::files::FileVersion;
+use crate::commands::migration::{DatabaseV4, SessionTab};
+use crate::commands::windows::MAIN_WINDOW;
+use crate::utils;
+
+/// Once the journal grows past this size it is compacted down to the latest
+/// entry per tab, provided it has at least doubled since the last compaction.
+const COMPACT_THRESHOLD: u64 = 8 * 1024 * 1024;
+
+/// One line of the journal.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry {
+    /// When the entry was written, unix milliseconds.
+    pub ts: u64,
+    /// Label of the window the tab was in.
+    #[serde(default = "main_window")]
+    pub window: String,
+    pub tab_id: String,
+    #[serde(flatten)]
+    pub change: TabChange,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum TabChange {
+    /// Full unsaved content of a tab.
+    #[serde(rename_all = "camelCase")]
+    Content {
+        filename: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        path: Option<String>,
+        content: String,
+        /// Version of the file the edits were made on.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        base_version: Option<FileVersion>,
+    },
+    /// The tab was closed; earlier content must not be recovered.
+    Closed,
+}
+
+/// Entries written before windows were tracked all came from the main window.
+fn main_window() -> String {
+    MAIN_WINDOW.to_string()
+}
+
+/// Write-ahead journal of unsaved tab contents.
+///
+/// `session.json` is only written when the frontend persists the session, so
+/// edits made since then live here, one JSON object per line. Each append is
+/// followed by an `fdatasync`, and a window's entries are dropped whenever
+/// that window's session is saved successfully, up to the time of the save.
+#[derive(Clone)]
+pub struct Journal {
+    path: PathBuf,
+    file: Arc<Mutex<Option<File>>>,
+    /// Size the journal was left at by the last compaction or clear. Latest
+    /// entries alone can exceed the threshold, and compacting those again on
+    /// every append would rewrite the whole file each time.
+    compacted_len: Arc<AtomicU64>,
+}
+
+impl Journal {
+    pub fn new(path: PathBuf) -> Self {
+        Journal {
+            path,
+            file: Arc::new(Mutex::new(None)),
+            compacted_len: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Append `entry` and flush it to disk.
+    pub fn append(&self, entry: &JournalEntry) -> Result<(), std::io::Error> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+
+        let mut guard = self.file.lock().unwrap();
+
+        if guard.is_none() {
+            if let Some(parent) = self.path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let mut file = OpenOptions::new()
+                .create(true)
+                .read(true)
+                .append(true)
+                .open(&self.path)?;
+            // Terminate a line torn by a crash so the next entry isn't glued to it.
+            if !ends_with_newline(&mut file)? {
+                file.write_all(b"\n")?;
+            }
+            *guard = Some(file);
+        }
+
+        let file = guard.as_mut().unwrap();
+        file.write_all(&line)?;
+        file.sync_data()?;
+
+        let len = file.metadata()?.len();
+        if len > COMPACT_THRESHOLD && len > 2 * self.compacted_len.load(Ordering::Relaxed) {
+            *guard = None;
+            self.compact()?;
+        }
+
+        Ok(())
+    }
+
+    /// Drop the entries of `window` written at or before `saved_at`. Called
+    /// once the session file holds the window as saved at that time; later
+    /// entries are edits the save didn't include.
+    pub fn clear_window(&self, window: &str, saved_at: u64) -> Result<(), std::io::Error> {
+        let mut guard = self.file.lock().unwrap();
+        *guard = None;
+
+        let others: Vec<JournalEntry> = self
+            .read_entries()
+            .into_iter()
+            .filter(|e| e.window != window || e.ts > saved_at)
+            .collect();
+
+        if others.is_empty() {
+            self.compacted_len.store(0, Ordering::Relaxed);
+            return match std::fs::remove_file(&self.path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            };
+        }
+
+        let len = write_entries(&self.path, &others)?;
+        self.compacted_len.store(len, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Read every well-formed entry. A line torn by a crash mid-write is
+    /// skipped, even one cut in the middle of a character, and the entries
+    /// after it are still read.
+    pub fn read_entries(&self) -> Vec<JournalEntry> {
+        let Ok(bytes) = std::fs::read(&self.path) else {
+            return Vec::new();
+        };
+
+        bytes
+            .split(|b| *b == b'\n')
+            .filter(|line| !line.is_empty())
+            .filter_map(|line| serde_json::from_slice(line).ok())
+            .collect()
+    }
+
+    /// Rewrite the journal keeping only the latest entry per tab.
+    fn compact(&self) -> Result<(), std::io::Error> {
+        let len = write_entries(&self.path, &latest_per_tab(self.read_entries()))?;
+        self.compacted_len.store(len, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// Replace the journal with `entries`, returning the size written.
+fn write_entries(path: &Path, entries: &[JournalEntry]) -> Result<u64, std::io::Error> {
+    let mut out = Vec::new();
+    for entry in entries {
+        serde_json::to_writer(&mut out, entry)?;
+        out.push(b'\n');
+    }
+
+    let len = out.len() as u64;
+    utils::atomic_write(path, out)?;
+    Ok(len)
+}
+
+/// Whether `file` is empty or ends with a newline.
+fn ends_with_newline(file: &mut File) -> Result<bool, std::io::Error> {
+    if file.metadata()?.len() == 0 {
+        return Ok(true);
+    }
+
+    let mut last = [0u8; 1];
+    file.seek(SeekFrom::End(-1))?;
+    file.read_exact(&mut last)?;
+    Ok(last[0] == b'\n')
+}
+
+/// Keep only the newest entry for each tab in each window, in the order tabs
+/// first appeared. A tab moved to another window has entries in both.
+fn latest_per_tab(entries: Vec<JournalEntry>) -> Vec<JournalEntry> {
+    let mut latest: Vec<JournalEntry> = Vec::new();
+
+    for entry in entries {
+        match latest
+            .iter_mut()
+            .find(|e| e.tab_id == entry.tab_id && e.window == entry.window)
+        {
+            Some(existing) if existing.ts <= entry.ts => *existing = entry,
+            Some(_) => {}
+            None => latest.push(entry),
+        }
+    }
+
+    latest
+}
+
+/// Apply journal entries to the loaded session, each to its own window.
+///
+/// Only entries newer than the window's last save are applied; for a window
+/// without a recorded save time, newer than `session_modified` (unix ms).
+/// Content entries update the matching tab (or recreate it if the tab was
+/// opened after the last session save) and mark it dirty. Closed entries
+/// remove the tab. Returns how many tabs were affected.
+pub fn replay(db: &mut DatabaseV4, entries: Vec<JournalEntry>, session_modified: u64) -> usize {
+    let since = |window: &str| match db.window(window) {
+        Some(w) if w.saved_at > 0 => w.saved_at,
+        _ => session_modified,
+    };
+    let recent: Vec<JournalEntry> = entries
+        .into_iter()
+        .filter(|e| e.ts > since(&e.window))
+        .collect();
+    let latest = latest_per_tab(recent);
+    let count = latest.len();
+
+    for entry in latest {
+        let session = match (&entry.change, db.window(&entry.window)) {
+            // Nothing to remove from a window that wasn't saved.
+            (TabChange::Closed, None) => continue,
+            _ => &mut db.window_mut(&entry.window).session,
+        };
+        let tabs = &mut session.tabs;
+        let position = tabs.iter().position(|t| t.id == entry.tab_id);
+
+        match (entry.change, position) {
+            (TabChange::Closed, Some(i)) => {
+                tabs.remove(i);
+            }
+            (TabChange::Closed, None) => {}
+            (
+                TabChange::Content {
+                    filename,
+                    path,
+                    content,
+                    base_version,
+                },
+                Some(i),
+            ) => {
+                let tab = &mut tabs[i];
+                tab.filename = filename;
+                tab.path = path;
+                tab.content = Some(content);
+                tab.is_dirty = true;
+                tab.base_version = base_version.or(tab.base_version.take());
+            }
+            (
+                TabChange::Content {
+                    filename,
+                    path,
+                    content,
+                    base_version,
+                },
+                None,
+            ) => tabs.push(SessionTab {
+                id: entry.tab_id,
+                path,
+                filename,
+                is_dirty: true,
+                content: Some(content),
+                buffer: None,
+                base_version,
+                view: None,
+            }),
+        }
+    }
+
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn content(ts: u64, tab_id: &str, content: &str) -> JournalEntry {
+        JournalEntry {
+            ts,
+            window: MAIN_WINDOW.to_string(),
+            tab_id: tab_id.to_string(),
+            change: TabChange::Content {
+                filename: "note.md".to_string(),
+                path: None,
+                content: content.to_string(),
+                base_version: None,
+            },
+        }
+    }
+
+    #[test]
+    fn entries_after_a_line_torn_mid_character_are_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Journal::new(dir.path().join("journal.jsonl"));
+
+        journal.append(&content(1, "a", "first")).unwrap();
+
+        // A crash cut the next write in the middle of "é".
+        let torn = serde_json::to_vec(&content(2, "b", "café")).unwrap();
+        let cut = torn.iter().position(|b| *b == 0xC3).unwrap() + 1;
+        let mut file = OpenOptions::new().append(true).open(&journal.path).unwrap();
+        file.write_all(&torn[..cut]).unwrap();
+        drop(file);
+
+        let journal = Journal::new(journal.path.clone());
+        journal.append(&content(3, "c", "third")).unwrap();
+
+        let ids: Vec<String> = journal
+            .read_entries()
+            .into_iter()
+            .map(|e| e.tab_id)
+            .collect();
+        assert_eq!(ids, ["a", "c"]);
+    }
+
+    #[test]
+    fn clear_window_keeps_entries_newer_than_the_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Journal::new(dir.path().join("journal.jsonl"));
+
+        journal.append(&content(1, "a", "saved")).unwrap();
+        journal.append(&content(5, "a", "after the save")).unwrap();
+        journal.clear_window(MAIN_WINDOW, 3).unwrap();
+
+        let entries = journal.read_entries();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].ts, 5);
+
+        journal.clear_window(MAIN_WINDOW, 5).unwrap();
+        assert!(!journal.path.exists());
+    }
+}
@@ -1,62 +1,188 @@
+mod buffers;
+mod cli;
 mod commands;
+mod encoding;
+mod handoff;
+mod headless;
+mod journal;
+mod line_ending;
+mod note_metadata;
+mod salvage;
+mod schema;
+mod snapshots;
 mod utils;
+mod v1_migration;
+mod wait;
+mod watcher;
 
+use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Mutex;
 
-use commands::{files, migration};
-use tauri::{Emitter, Manager};
+use cli::{Invocation, OpenRequest};
+use commands::migration::SessionState;
+use commands::settings::SettingsLock;
+use commands::windows::{self, FocusedWindow, MAIN_WINDOW};
+use commands::{
+    closed_tabs, files, history, migration, recent_files, recovery, settings, view_state,
+    workspaces,
+};
+use journal::Journal;
+use tauri::{Manager, WindowEvent};
+use wait::Waiters;
+use watcher::FileWatcher;
 
-/// Holds file paths passed via CLI arguments on cold start.
-/// The frontend calls `take_cli_file_paths` once during initialization
-/// to drain and consume these paths.
-pub struct CliFilePaths(pub Mutex<Vec<String>>);
+/// Holds the open requests waiting for a window to load, by window label:
+/// the CLI arguments on cold start, or what a new window was opened for.
+/// Each window calls `take_cli_file_paths` once during initialization to
+/// drain and consume its own.
+pub struct CliFilePaths(pub Mutex<HashMap<String, Vec<OpenRequest>>>);
+
+/// Parse a command line (without the executable) run from `cwd`, reporting
+/// errors instead of failing so a typo never keeps the editor from starting.
+fn parse_invocation(args: &[String], cwd: &Path) -> Invocation {
+    cli::parse_args(args.iter().cloned(), cwd).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        Invocation::default()
+    })
+}
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // Extract file paths from CLI args (skip the exe path at args[0]).
-    // Filter out flags (--something / -x) so we only keep paths.
-    let cli_file_paths: Vec<String> = std::env::args()
-        .skip(1)
-        .filter(|arg| !arg.starts_with('-'))
-        .collect();
+    // Parse CLI args, skipping the exe path at args[0].
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+
+    // Subcommands like `recent` print JSON and exit without opening a window.
+    if let Some(code) = headless::run(&args) {
+        std::process::exit(code);
+    }
+
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let invocation = parse_invocation(&args, &cwd);
+    let waits = invocation.wait && !invocation.requests.is_empty();
+
+    // Read piped input before the single-instance check, so that if another
+    // instance is already running it can pick the input up from the spool.
+    // Only arguments are forwarded, so a copy of this process carrying the
+    // spool's name takes over.
+    if cli::reads_stdin(&args) {
+        match cli::spool_stdin() {
+            Ok(spool) => {
+                args.insert(0, spool);
+                if !waits {
+                    cli::relaunch(&args);
+                }
+            }
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+
+    // With `--wait` this process only waits; a copy of it opens the files.
+    if waits {
+        wait::run_waiter(&args);
+    }
 
     tauri::Builder::default()
-        .manage(CliFilePaths(Mutex::new(cli_file_paths)))
-        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+        .manage(CliFilePaths(Mutex::new(HashMap::new())))
+        .manage(Waiters::default())
+        .manage(SessionState::default())
+        .manage(SettingsLock::default())
+        .manage(FocusedWindow::default())
+        .plugin(tauri_plugin_single_instance::init(|app, args, cwd| {
             // When a second instance is launched (e.g. "Open with" while
-            // the app is already running), extract file paths from its
-            // args and forward them to the running frontend via an event.
-            let file_paths: Vec<&str> = args
-                .iter()
-                .skip(1)
-                .filter(|arg| !arg.starts_with('-'))
-                .map(|s| s.as_str())
-                .collect();
-
-            if !file_paths.is_empty() {
-                let _ = app.emit("open-files", &file_paths);
-            }
+            // the app is already running), parse its args and forward the
+            // open requests to the last focused window via an event, or to a
+            // new window for `--new-window`. Relative paths are resolved
+            // against the second instance's cwd.
+            let args = args.get(1..).unwrap_or_default();
+            let cwd = Path::new(&cwd);
+            let mut invocation = parse_invocation(args, cwd);
+            invocation.register_wait(&app.state::<Waiters>());
+            cli::attach_stdin(&mut invocation);
 
-            // Bring the existing window to the foreground.
-            if let Some(window) = app.get_webview_window("main") {
-                let _ = window.unminimize();
-                let _ = window.set_focus();
-            }
+            windows::forward_requests(app, invocation.requests, invocation.new_window);
         }))
         .plugin(tauri_plugin_window_state::Builder::new().build())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_opener::init())
+        .setup(move |app| {
+            // Only the primary instance gets here, so only it may consume the
+            // spooled stdin; a second instance leaves it for us to pick up.
+            let mut invocation = invocation;
+            invocation.register_wait(&app.state::<Waiters>());
+            cli::attach_stdin(&mut invocation);
+            app.state::<CliFilePaths>()
+                .0
+                .lock()
+                .unwrap()
+                .insert(MAIN_WINDOW.to_string(), invocation.requests);
+
+            app.manage(FileWatcher::new(app.handle().clone()));
+            app.manage(Journal::new(migration::get_journal_path(app.handle())));
+            Ok(())
+        })
+        .on_window_event(|window, event| match event {
+            WindowEvent::Focused(true) => {
+                *window.state::<FocusedWindow>().0.lock().unwrap() =
+                    Some(window.label().to_string());
+            }
+            WindowEvent::Destroyed => {
+                window.state::<FileWatcher>().forget_window(window.label());
+            }
+            _ => {}
+        })
         .invoke_handler(tauri::generate_handler![
+            closed_tabs::record_closed_tab,
+            closed_tabs::list_closed_tabs,
+            closed_tabs::reopen_closed_tab,
             files::save_file,
             files::open_file,
+            files::convert_line_endings,
+            files::watch_open_files,
             files::take_cli_file_paths,
+            files::finish_wait,
+            files::diff_files,
+            history::list_file_versions,
+            history::read_file_version,
+            history::diff_file_version,
+            history::restore_file_version,
             migration::check_for_migration_to_v2,
+            migration::dry_run_v1_to_v2,
             migration::migrate_v1_to_v2,
+            migration::rollback_v1_to_v2,
+            migration::list_failed_v1_notes,
+            migration::retry_failed_v1_notes,
+            migration::get_note_metadata,
+            migration::list_note_metadata,
             migration::save_editor_state,
             migration::load_editor_state,
+            recent_files::record_file_opened,
+            recent_files::list_recent_files,
+            recent_files::set_recent_file_pinned,
+            recovery::journal_tab_content,
+            recovery::journal_tab_closed,
+            settings::get_settings,
+            settings::update_settings,
+            view_state::remember_view_state,
+            view_state::get_view_state,
+            windows::new_window,
+            windows::move_tab_to_window,
+            workspaces::list_workspaces,
+            workspaces::create_workspace,
+            workspaces::rename_workspace,
+            workspaces::duplicate_workspace,
+            workspaces::delete_workspace,
+            workspaces::switch_workspace,
+            workspaces::detach_window,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Don't leave `--wait` callers hanging once we're gone.
+            if let tauri::RunEvent::Exit = event {
+                app.state::<Waiters>().finish_all();
+            }
+        });
 }
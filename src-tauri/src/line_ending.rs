@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+
+/// A concrete line terminator.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    pub fn label(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "LF",
+            LineEnding::Crlf => "CRLF",
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+}
+
+/// What line endings `save_file` should write.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEndingPolicy {
+    /// Keep the style of the file on disk. New files and files with mixed
+    /// endings are written exactly as received.
+    #[default]
+    Preserve,
+    Lf,
+    Crlf,
+}
+
+impl LineEndingPolicy {
+    /// Resolve the policy against the content currently on disk (if any).
+    /// `None` means "write the content untouched".
+    pub fn resolve(self, on_disk: Option<&str>) -> Option<LineEnding> {
+        match self {
+            LineEndingPolicy::Lf => Some(LineEnding::Lf),
+            LineEndingPolicy::Crlf => Some(LineEnding::Crlf),
+            LineEndingPolicy::Preserve => match on_disk.map(detect_line_ending) {
+                Some("CRLF") => Some(LineEnding::Crlf),
+                Some("LF") => Some(LineEnding::Lf),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Efficiently detect the dominant line-ending style by scanning raw bytes.
+///
+/// Returns early with `"Mixed"` as soon as both styles are found.
+pub fn detect_line_ending(content: &str) -> &'static str {
+    let bytes = content.as_bytes();
+    let mut has_crlf = false;
+    let mut has_lf_only = false;
+
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\r' && i + 1 < bytes.len() && bytes[i + 1] == b'\n' {
+            has_crlf = true;
+            i += 2;
+        } else if bytes[i] == b'\n' {
+            has_lf_only = true;
+            i += 1;
+        } else {
+            i += 1;
+        }
+
+        if has_crlf && has_lf_only {
+            return "Mixed";
+        }
+    }
+
+    match (has_crlf, has_lf_only) {
+        (true, false) => "CRLF",
+        (false, true) => "LF",
+        (true, true) => "Mixed",
+        (false, false) => "N/A",
+    }
+}
+
+/// Rewrite every line terminator (`\r\n`, `\n` or a lone `\r`) as `target`.
+///
+/// Returns the normalized text and how many terminators actually changed.
+pub fn normalize(content: &str, target: LineEnding) -> (String, usize) {
+    let mut out = String::with_capacity(content.len());
+    let mut changed = 0;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let original = match c {
+            '\r' if chars.peek() == Some(&'\n') => {
+                chars.next();
+                "\r\n"
+            }
+            '\r' => "\r",
+            '\n' => "\n",
+            _ => {
+                out.push(c);
+                continue;
+            }
+        };
+
+        if original != target.as_str() {
+            changed += 1;
+        }
+        out.push_str(target.as_str());
+    }
+
+    (out, changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_the_line_ending_style() {
+        assert_eq!(detect_line_ending("a\r\nb\r\n"), "CRLF");
+        assert_eq!(detect_line_ending("a\nb\n"), "LF");
+        assert_eq!(detect_line_ending("a\r\nb\nc"), "Mixed");
+        assert_eq!(detect_line_ending("no newline"), "N/A");
+    }
+
+    #[test]
+    fn normalizes_to_lf() {
+        assert_eq!(
+            normalize("a\r\nb\r\n", LineEnding::Lf),
+            ("a\nb\n".to_string(), 2)
+        );
+        assert_eq!(
+            normalize("a\nb\n", LineEnding::Lf),
+            ("a\nb\n".to_string(), 0)
+        );
+    }
+
+    #[test]
+    fn normalizes_to_crlf() {
+        assert_eq!(
+            normalize("a\nb\n", LineEnding::Crlf),
+            ("a\r\nb\r\n".to_string(), 2)
+        );
+        assert_eq!(
+            normalize("a\r\nb", LineEnding::Crlf),
+            ("a\r\nb".to_string(), 0)
+        );
+    }
+
+    #[test]
+    fn mixed_endings_and_lone_cr_are_all_rewritten() {
+        let (lf, changed) = normalize("a\r\nb\nc\rd", LineEnding::Lf);
+        assert_eq!(lf, "a\nb\nc\nd");
+        assert_eq!(changed, 2);
+
+        let (crlf, changed) = normalize("a\r\nb\nc\rd", LineEnding::Crlf);
+        assert_eq!(crlf, "a\r\nb\r\nc\r\nd");
+        assert_eq!(changed, 2);
+    }
+
+    #[test]
+    fn preserve_keeps_the_style_on_disk() {
+        let policy = LineEndingPolicy::Preserve;
+
+        assert_eq!(policy.resolve(Some("a\r\nb")), Some(LineEnding::Crlf));
+        assert_eq!(policy.resolve(Some("a\nb")), Some(LineEnding::Lf));
+        assert_eq!(policy.resolve(Some("a\r\nb\n")), None);
+        assert_eq!(policy.resolve(None), None);
+        assert_eq!(
+            LineEndingPolicy::Lf.resolve(Some("a\r\nb")),
+            Some(LineEnding::Lf)
+        );
+    }
+}
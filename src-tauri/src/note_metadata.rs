@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::migration::NoteV1;
+use crate::utils;
+
+/// What V1 knew about a note that plain files can't carry: its title, tag
+/// and creation date.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteMetadata {
+    pub title: String,
+    pub tag: String,
+    /// Unix milliseconds.
+    pub created_at: u64,
+    /// Unix milliseconds.
+    pub updated_at: u64,
+}
+
+impl From<&NoteV1> for NoteMetadata {
+    fn from(note: &NoteV1) -> Self {
+        NoteMetadata {
+            title: note.title.clone(),
+            tag: note.tag.clone(),
+            created_at: note.created_at,
+            updated_at: note.updated_at,
+        }
+    }
+}
+
+/// Sidecar store, keyed by file path.
+pub type MetadataStore = HashMap<String, NoteMetadata>;
+
+pub fn metadata_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("notes-metadata.json")
+}
+
+/// Read the store. A missing or unreadable file is an empty store.
+pub fn read_store(path: &Path) -> MetadataStore {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn write_store(path: &Path, store: &MetadataStore) -> Result<(), String> {
+    let serialized =
+        serde_json::to_string_pretty(store).map_err(|e| format!("Serialization error: {}", e))?;
+    utils::atomic_write(path, serialized)
+        .map_err(|e| format!("Error writing {}: {}", path.display(), e))
+}
@@ -0,0 +1,237 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::commands::migration::{
+    ClosedTab, DatabaseV4, EditorSession, LocalFile, RememberedView, SessionTab, WindowSession,
+    Workspace,
+};
+use crate::commands::windows::MAIN_WINDOW;
+
+/// What could be rescued from a session file that failed to parse.
+pub struct Salvaged {
+    pub db: DatabaseV4,
+    pub recovered_tabs: usize,
+    pub recovered_recent_files: usize,
+}
+
+/// Best-effort recovery of a corrupt `session.json`.
+///
+/// Every recent file, workspace and tab is parsed on its own so one bad entry
+/// doesn't cost the rest. Anything unparseable is dropped. A file cut short,
+/// say by a full disk, is read up to the last complete object or array.
+pub fn salvage(raw: &str) -> Salvaged {
+    let mut db = DatabaseV4::default();
+
+    let parsed = serde_json::from_str::<Value>(raw).ok();
+    let Some(value) = parsed.or_else(|| parse_prefix(raw)) else {
+        return Salvaged {
+            db,
+            recovered_tabs: 0,
+            recovered_recent_files: 0,
+        };
+    };
+
+    if let Some(recent) = value.get("recentFiles").and_then(Value::as_object) {
+        for (path, entry) in recent {
+            if let Ok(file) = serde_json::from_value::<LocalFile>(entry.clone()) {
+                db.recent_files.insert(path.clone(), file);
+            }
+        }
+    }
+
+    if let Some(views) = value.get("rememberedViews").and_then(Value::as_array) {
+        db.remembered_views = views
+            .iter()
+            .filter_map(|view| serde_json::from_value::<RememberedView>(view.clone()).ok())
+            .collect();
+    }
+
+    if let Some(closed) = value.get("closedTabs").and_then(Value::as_array) {
+        db.closed_tabs = closed
+            .iter()
+            .filter_map(|tab| serde_json::from_value::<ClosedTab>(tab.clone()).ok())
+            .collect();
+    }
+
+    if let Some(workspaces) = value.get("workspaces").and_then(Value::as_array) {
+        for saved in workspaces {
+            let id = saved.get("id").and_then(Value::as_str);
+            let name = saved.get("name").and_then(Value::as_str);
+
+            if let (Some(id), Some(name)) = (id, name) {
+                db.workspaces.push(Workspace {
+                    id: id.to_string(),
+                    name: name.to_string(),
+                    parked: saved.get("parked").map(salvage_session),
+                });
+            }
+        }
+    }
+
+    let windows: Vec<(&str, &Value)> = match value.get("windows").and_then(Value::as_array) {
+        Some(windows) => windows
+            .iter()
+            .map(|w| {
+                (
+                    w.get("label")
+                        .and_then(Value::as_str)
+                        .unwrap_or(MAIN_WINDOW),
+                    w,
+                )
+            })
+            .collect(),
+        // Before V3 there was a single tab list.
+        None => value
+            .get("session")
+            .map(|session| vec![(MAIN_WINDOW, session)])
+            .unwrap_or_default(),
+    };
+
+    for (label, saved) in windows {
+        if db.window(label).is_some() {
+            continue;
+        }
+
+        let workspace = saved.get("workspace").and_then(Value::as_str);
+        let mut window = WindowSession::new(label, workspace.unwrap_or_default().to_string());
+        window.session = salvage_session(saved);
+        window.geometry = saved
+            .get("geometry")
+            .and_then(|g| serde_json::from_value(g.clone()).ok());
+        db.windows.push(window);
+    }
+
+    // Windows whose workspace was lost get a new one.
+    db.repair_workspaces();
+
+    Salvaged {
+        recovered_tabs: db.tabs().count(),
+        recovered_recent_files: db.recent_files.len(),
+        db,
+    }
+}
+
+/// Most cuts to try closing before giving up on a truncated file.
+const MAX_PREFIX_ATTEMPTS: usize = 64;
+
+/// Parse the longest prefix of `raw` that ends right after an object or
+/// array, with the brackets still open at that point closed. Values cut off
+/// mid-way are dropped along with the rest of the text.
+fn parse_prefix(raw: &str) -> Option<Value> {
+    let mut open = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    // Where each object or array ended, and the closers needed there.
+    let mut cuts: Vec<(usize, String)> = Vec::new();
+
+    for (i, b) in raw.bytes().enumerate() {
+        if in_string {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match b {
+            b'"' => in_string = true,
+            b'{' => open.push(b'}'),
+            b'[' => open.push(b']'),
+            b'}' | b']' => {
+                open.pop()?;
+                let closers = open.iter().rev().map(|c| *c as char).collect();
+                cuts.push((i + 1, closers));
+            }
+            _ => {}
+        }
+    }
+
+    cuts.iter()
+        .rev()
+        .filter(|(_, closers)| !closers.is_empty())
+        .take(MAX_PREFIX_ATTEMPTS)
+        .find_map(|(end, closers)| {
+            serde_json::from_str(&format!("{}{}", &raw[..*end], closers)).ok()
+        })
+}
+
+/// The tabs of a window or workspace, skipping any that don't parse.
+fn salvage_session(saved: &Value) -> EditorSession {
+    let tabs: Vec<SessionTab> = saved
+        .get("tabs")
+        .and_then(Value::as_array)
+        .map(|tabs| {
+            tabs.iter()
+                .filter_map(|tab| serde_json::from_value(tab.clone()).ok())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let current_tab_id = saved
+        .get("currentTabId")
+        .and_then(Value::as_str)
+        .filter(|id| tabs.iter().any(|t| t.id == *id))
+        .map(str::to_string);
+
+    EditorSession {
+        tabs,
+        current_tab_id,
+    }
+}
+
+/// Move a corrupt file aside as `<stem>.corrupt-<timestamp>.<ext>` in the
+/// same directory and return the new path.
+pub fn quarantine(path: &Path) -> Result<PathBuf, std::io::Error> {
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("session");
+    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("json");
+    let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+
+    let target = path.with_file_name(format!("{}.corrupt-{}.{}", stem, timestamp, ext));
+    std::fs::rename(path, &target)?;
+
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SESSION: &str = r#"{
+  "schemaVersion": "V4",
+  "recentFiles": {
+    "/notes/a.md": { "id": "1", "filename": "a.md", "modified": 3, "path": "/notes/a.md" }
+  },
+  "workspaces": [{ "id": "w1", "name": "Notes" }],
+  "windows": [
+    {
+      "label": "main",
+      "workspace": "w1",
+      "tabs": [
+        { "id": "t1", "filename": "a.md", "path": "/notes/a.md", "isDirty": false },
+        { "id": "t2", "filename": "Untitled", "isDirty": true, "content": "a \"quoted\" {brace"#;
+
+    #[test]
+    fn truncated_session_keeps_what_came_before_the_cut() {
+        let salvaged = salvage(SESSION);
+
+        assert_eq!(salvaged.recovered_recent_files, 1);
+        assert_eq!(salvaged.recovered_tabs, 1);
+        let main = salvaged.db.window(MAIN_WINDOW).unwrap();
+        assert_eq!(main.workspace, "w1");
+        assert_eq!(main.session.tabs[0].id, "t1");
+    }
+
+    #[test]
+    fn text_without_any_complete_value_recovers_nothing() {
+        let salvaged = salvage("{ \"recentFiles\": { \"/notes/a.md\": { \"id");
+
+        assert_eq!(salvaged.recovered_recent_files, 0);
+        assert_eq!(salvaged.recovered_tabs, 0);
+    }
+}
@@ -0,0 +1,360 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::commands::migration::SchemaVersion;
+use crate::commands::windows::MAIN_WINDOW;
+
+/// Version written by this build. Bump it together with a new entry in
+/// [`STEPS`] whenever the stored session format changes.
+pub const CURRENT_VERSION: SchemaVersion = SchemaVersion::V4;
+
+/// A single typed upgrade of the stored session from one version to the next.
+///
+/// `Old` and `New` are snapshots of the stored format at each version, kept
+/// separate from the live types so old steps keep compiling as the live
+/// types evolve.
+pub trait Migration {
+    const FROM: SchemaVersion;
+    const TO: SchemaVersion;
+    type Old: DeserializeOwned;
+    type New: Serialize;
+
+    fn migrate(old: Self::Old) -> Self::New;
+}
+
+/// Type-erased migration step, so steps with different types fit in one table.
+struct Step {
+    from: SchemaVersion,
+    to: SchemaVersion,
+    apply: fn(Value) -> Result<Value, String>,
+}
+
+impl Step {
+    const fn of<M: Migration>() -> Self {
+        Step {
+            from: M::FROM,
+            to: M::TO,
+            apply: apply::<M>,
+        }
+    }
+}
+
+/// Every known step, in order. Each one's `from` must be the previous `to`.
+const STEPS: &[Step] = &[Step::of::<SplitIntoWindows>(), Step::of::<AddWorkspaces>()];
+
+fn apply<M: Migration>(value: Value) -> Result<Value, String> {
+    let old: M::Old = serde_json::from_value(value)
+        .map_err(|e| format!("Error reading {:?} data: {}", M::FROM, e))?;
+
+    let mut new = serde_json::to_value(M::migrate(old))
+        .map_err(|e| format!("Error writing {:?} data: {}", M::TO, e))?;
+
+    if let Value::Object(map) = &mut new {
+        map.insert("schemaVersion".to_string(), version_value(M::TO));
+    }
+
+    Ok(new)
+}
+
+/// V2 → V3: the single tab list becomes the first window of a window list.
+struct SplitIntoWindows;
+
+mod v2 {
+    use super::*;
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Database {
+        pub recent_files: Map<String, Value>,
+        pub session: Map<String, Value>,
+    }
+}
+
+mod v3 {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Database {
+        pub recent_files: Map<String, Value>,
+        pub windows: Vec<Window>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct Window {
+        pub label: String,
+        /// `tabs` and `currentTabId`, unchanged.
+        #[serde(flatten)]
+        pub session: Map<String, Value>,
+    }
+}
+
+impl Migration for SplitIntoWindows {
+    const FROM: SchemaVersion = SchemaVersion::V2;
+    const TO: SchemaVersion = SchemaVersion::V3;
+    type Old = v2::Database;
+    type New = v3::Database;
+
+    fn migrate(old: v2::Database) -> v3::Database {
+        v3::Database {
+            recent_files: old.recent_files,
+            windows: vec![v3::Window {
+                label: MAIN_WINDOW.to_string(),
+                session: old.session,
+            }],
+        }
+    }
+}
+
+/// V3 → V4: each window's tabs become a workspace of their own, the first
+/// one named "Default".
+struct AddWorkspaces;
+
+mod v4 {
+    use super::*;
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Database {
+        pub recent_files: Map<String, Value>,
+        pub workspaces: Vec<Workspace>,
+        pub windows: Vec<Window>,
+    }
+
+    #[derive(Serialize)]
+    pub struct Workspace {
+        pub id: String,
+        pub name: String,
+    }
+
+    #[derive(Serialize)]
+    pub struct Window {
+        pub label: String,
+        pub workspace: String,
+        /// `tabs`, `currentTabId`, `geometry` and `savedAt`, unchanged.
+        #[serde(flatten)]
+        pub rest: Map<String, Value>,
+    }
+}
+
+impl Migration for AddWorkspaces {
+    const FROM: SchemaVersion = SchemaVersion::V3;
+    const TO: SchemaVersion = SchemaVersion::V4;
+    type Old = v3::Database;
+    type New = v4::Database;
+
+    fn migrate(old: v3::Database) -> v4::Database {
+        let mut workspaces = Vec::new();
+        let mut windows = Vec::new();
+
+        for (index, window) in old.windows.into_iter().enumerate() {
+            let id = uuid::Uuid::new_v4().to_string();
+            let name = match index {
+                0 => "Default".to_string(),
+                n => format!("Workspace {}", n + 1),
+            };
+
+            workspaces.push(v4::Workspace {
+                id: id.clone(),
+                name,
+            });
+            windows.push(v4::Window {
+                label: window.label,
+                workspace: id,
+                rest: window.session,
+            });
+        }
+
+        v4::Database {
+            recent_files: old.recent_files,
+            workspaces,
+            windows,
+        }
+    }
+}
+
+fn version_value(version: SchemaVersion) -> Value {
+    serde_json::to_value(version).unwrap_or(Value::Null)
+}
+
+/// Read the `schemaVersion` field of stored session data.
+pub fn detect_version(value: &Value) -> Result<SchemaVersion, String> {
+    let field = value
+        .get("schemaVersion")
+        .ok_or_else(|| "Missing schemaVersion field".to_string())?;
+
+    serde_json::from_value(field.clone()).map_err(|_| {
+        format!(
+            "Unknown schema version {}; the file may come from a newer version of the app",
+            field
+        )
+    })
+}
+
+/// Upgrade stored session data to [`CURRENT_VERSION`] by running every step
+/// from its detected version onwards.
+///
+/// Returns the upgraded data and the version it started from.
+pub fn migrate_to_current(mut value: Value) -> Result<(Value, SchemaVersion), String> {
+    let original = detect_version(&value)?;
+    let mut version = original;
+
+    while version != CURRENT_VERSION {
+        let step = STEPS
+            .iter()
+            .find(|s| s.from == version)
+            .ok_or_else(|| format!("No migration path from {:?}", version))?;
+
+        value = (step.apply)(value)?;
+        version = step.to;
+    }
+
+    Ok((value, original))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::commands::migration::DatabaseV4;
+
+    fn v2_session() -> Value {
+        json!({
+            "schemaVersion": "V2",
+            "recentFiles": {
+                "/notes/a.md": { "id": "1", "filename": "a.md", "modified": 3, "path": "/notes/a.md" }
+            },
+            "session": {
+                "tabs": [{ "id": "t1", "filename": "a.md", "path": "/notes/a.md", "isDirty": false }],
+                "currentTabId": "t1"
+            }
+        })
+    }
+
+    fn v3_session() -> Value {
+        json!({
+            "schemaVersion": "V3",
+            "recentFiles": {},
+            "windows": [
+                { "label": "main", "tabs": [{ "id": "t1", "filename": "a", "isDirty": true, "content": "x" }], "currentTabId": "t1", "savedAt": 7 },
+                { "label": "window-2", "tabs": [] }
+            ]
+        })
+    }
+
+    #[test]
+    fn steps_form_a_chain_to_the_current_version() {
+        assert!(!STEPS.is_empty());
+        for pair in STEPS.windows(2) {
+            assert_eq!(pair[1].from, pair[0].to);
+        }
+        assert_eq!(STEPS.last().map(|s| s.to), Some(CURRENT_VERSION));
+    }
+
+    #[test]
+    fn detect_version_reads_known_versions() {
+        assert_eq!(detect_version(&v2_session()), Ok(SchemaVersion::V2));
+        assert_eq!(detect_version(&v3_session()), Ok(SchemaVersion::V3));
+        assert_eq!(
+            detect_version(&json!({ "schemaVersion": "V4" })),
+            Ok(SchemaVersion::V4)
+        );
+    }
+
+    #[test]
+    fn detect_version_rejects_missing_and_unknown_versions() {
+        let missing = detect_version(&json!({ "recentFiles": {} })).unwrap_err();
+        assert!(missing.contains("Missing schemaVersion"));
+
+        let future = detect_version(&json!({ "schemaVersion": "V99" })).unwrap_err();
+        assert!(future.contains("newer version"));
+
+        let garbage = detect_version(&json!({ "schemaVersion": 4 })).unwrap_err();
+        assert!(garbage.contains("Unknown schema version"));
+    }
+
+    #[test]
+    fn split_into_windows_moves_the_session_into_the_main_window() {
+        let old: v2::Database = serde_json::from_value(v2_session()).unwrap();
+        let new = serde_json::to_value(SplitIntoWindows::migrate(old)).unwrap();
+
+        assert_eq!(new["recentFiles"], v2_session()["recentFiles"]);
+        assert_eq!(new["windows"].as_array().map(Vec::len), Some(1));
+        assert_eq!(new["windows"][0]["label"], MAIN_WINDOW);
+        assert_eq!(new["windows"][0]["tabs"], v2_session()["session"]["tabs"]);
+        assert_eq!(new["windows"][0]["currentTabId"], "t1");
+    }
+
+    #[test]
+    fn add_workspaces_gives_each_window_its_own_workspace() {
+        let old: v3::Database = serde_json::from_value(v3_session()).unwrap();
+        let new = serde_json::to_value(AddWorkspaces::migrate(old)).unwrap();
+
+        let workspaces = new["workspaces"].as_array().unwrap();
+        let windows = new["windows"].as_array().unwrap();
+        assert_eq!(workspaces.len(), 2);
+        assert_eq!(workspaces[0]["name"], "Default");
+        assert_eq!(workspaces[1]["name"], "Workspace 2");
+        assert_ne!(workspaces[0]["id"], workspaces[1]["id"]);
+
+        for (window, workspace) in windows.iter().zip(workspaces) {
+            assert_eq!(window["workspace"], workspace["id"]);
+        }
+        assert_eq!(windows[0]["label"], "main");
+        assert_eq!(windows[0]["savedAt"], 7);
+        assert_eq!(windows[0]["tabs"][0]["content"], "x");
+        assert_eq!(windows[1]["label"], "window-2");
+    }
+
+    #[test]
+    fn migrates_v2_to_current() {
+        let (value, from) = migrate_to_current(v2_session()).unwrap();
+
+        assert_eq!(from, SchemaVersion::V2);
+        assert_eq!(detect_version(&value), Ok(CURRENT_VERSION));
+
+        let db: DatabaseV4 = serde_json::from_value(value).unwrap();
+        assert_eq!(db.workspaces.len(), 1);
+        assert_eq!(db.windows.len(), 1);
+        assert_eq!(db.windows[0].label, MAIN_WINDOW);
+        assert_eq!(db.windows[0].workspace, db.workspaces[0].id);
+        assert_eq!(db.windows[0].session.tabs[0].id, "t1");
+        assert!(db.recent_files.contains_key("/notes/a.md"));
+    }
+
+    #[test]
+    fn migrates_v3_to_v4() {
+        let (value, from) = migrate_to_current(v3_session()).unwrap();
+
+        assert_eq!(from, SchemaVersion::V3);
+        assert_eq!(value["schemaVersion"], "V4");
+
+        let db: DatabaseV4 = serde_json::from_value(value).unwrap();
+        assert_eq!(db.workspaces.len(), 2);
+        assert_eq!(db.windows[0].saved_at, 7);
+        assert_eq!(db.windows[0].session.tabs[0].content.as_deref(), Some("x"));
+    }
+
+    #[test]
+    fn current_data_is_left_unchanged() {
+        let current = json!({ "schemaVersion": "V4", "recentFiles": {}, "windows": [] });
+        let (value, from) = migrate_to_current(current.clone()).unwrap();
+
+        assert_eq!(from, CURRENT_VERSION);
+        assert_eq!(value, current);
+    }
+
+    #[test]
+    fn data_without_a_migration_path_is_refused() {
+        let err = migrate_to_current(json!({ "schemaVersion": "V1" })).unwrap_err();
+        assert!(err.contains("No migration path"));
+    }
+
+    #[test]
+    fn malformed_data_is_refused() {
+        let err = migrate_to_current(json!({ "schemaVersion": "V2", "session": {} })).unwrap_err();
+        assert!(err.contains("Error reading V2 data"));
+    }
+}
@@ -0,0 +1,294 @@
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{self, content_hash};
+
+/// Serializes every read-modify-write of the history indexes.
+static HISTORY_LOCK: Mutex<()> = Mutex::new(());
+
+const INDEX_FILE: &str = "index.json";
+
+/// One stored snapshot of a file's previous contents.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Snapshot {
+    pub id: String,
+    /// When the snapshot was taken, unix milliseconds.
+    pub created_at: u64,
+    /// Uncompressed size in bytes.
+    pub size: u64,
+    /// Size of the compressed blob on disk.
+    pub stored_size: u64,
+    /// Hex SHA-256 of the uncompressed bytes.
+    pub hash: String,
+}
+
+/// Per-file index, stored as `index.json` in the file's history directory.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+struct HistoryIndex {
+    path: String,
+    /// Oldest first.
+    snapshots: Vec<Snapshot>,
+}
+
+/// Limits applied to the snapshot store. A value of `0` disables that limit.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RetentionPolicy {
+    /// Snapshots kept per file.
+    pub max_versions: usize,
+    /// Snapshots older than this many days are deleted.
+    pub max_age_days: u64,
+    /// Total compressed size of all snapshots, across every file.
+    pub max_total_bytes: u64,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        RetentionPolicy {
+            max_versions: 50,
+            max_age_days: 30,
+            max_total_bytes: 200 * 1024 * 1024,
+        }
+    }
+}
+
+/// Directory holding the snapshots of `path` inside the history `root`.
+///
+/// Keyed by the canonical path, so a file reached through a symlink or a
+/// relative path shares one history. A path that no longer resolves is
+/// keyed as given, which for the absolute paths of open tabs is the same.
+fn history_dir(root: &Path, path: &str) -> PathBuf {
+    let key = fs::canonicalize(path)
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| path.to_string());
+    root.join(&content_hash(key.as_bytes())[..16])
+}
+
+fn read_index(dir: &Path, path: &str) -> HistoryIndex {
+    load_index(dir)
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| HistoryIndex {
+            path: path.to_string(),
+            snapshots: Vec::new(),
+        })
+}
+
+/// The index in `dir`, `None` when there is none. Unlike [`read_index`], an
+/// index that can't be read or parsed is an error rather than empty.
+fn load_index(dir: &Path) -> Result<Option<HistoryIndex>, String> {
+    let raw = match fs::read_to_string(dir.join(INDEX_FILE)) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Error reading history index: {}", e)),
+    };
+
+    serde_json::from_str(&raw)
+        .map(Some)
+        .map_err(|e| format!("Error parsing history index: {}", e))
+}
+
+fn write_index(dir: &Path, index: &HistoryIndex) -> Result<(), String> {
+    let serialized =
+        serde_json::to_string_pretty(index).map_err(|e| format!("Serialization error: {}", e))?;
+    utils::atomic_write(dir.join(INDEX_FILE), serialized)
+        .map_err(|e| format!("Error writing history index: {}", e))
+}
+
+fn blob_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{}.gz", id))
+}
+
+/// Store `bytes` as a new snapshot of `path`.
+///
+/// Consecutive identical snapshots are skipped, so saving an unchanged file
+/// doesn't grow the history.
+pub fn record_snapshot(root: &Path, path: &str, bytes: &[u8]) -> Result<Option<Snapshot>, String> {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+
+    let dir = history_dir(root, path);
+    let mut index = read_index(&dir, path);
+    let hash = content_hash(bytes);
+
+    if index.snapshots.last().is_some_and(|s| s.hash == hash) {
+        return Ok(None);
+    }
+
+    fs::create_dir_all(&dir).map_err(|e| format!("Error creating history dir: {}", e))?;
+
+    let created_at = chrono::Utc::now().timestamp_millis() as u64;
+    let id = format!("{}-{}", created_at, &hash[..8]);
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(bytes)
+        .map_err(|e| format!("Error compressing snapshot: {}", e))?;
+    let compressed = encoder
+        .finish()
+        .map_err(|e| format!("Error compressing snapshot: {}", e))?;
+
+    utils::atomic_write(blob_path(&dir, &id), &compressed)
+        .map_err(|e| format!("Error writing snapshot: {}", e))?;
+
+    let snapshot = Snapshot {
+        id,
+        created_at,
+        size: bytes.len() as u64,
+        stored_size: compressed.len() as u64,
+        hash,
+    };
+    index.snapshots.push(snapshot.clone());
+    write_index(&dir, &index)?;
+
+    Ok(Some(snapshot))
+}
+
+/// Snapshots of `path`, newest first.
+pub fn list_snapshots(root: &Path, path: &str) -> Vec<Snapshot> {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+
+    let mut snapshots = read_index(&history_dir(root, path), path).snapshots;
+    snapshots.reverse();
+    snapshots
+}
+
+/// Raw, decompressed bytes of one snapshot.
+pub fn read_snapshot(root: &Path, path: &str, id: &str) -> Result<Vec<u8>, String> {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+
+    let dir = history_dir(root, path);
+    if !read_index(&dir, path).snapshots.iter().any(|s| s.id == id) {
+        return Err(format!("No version {} for {}", id, path));
+    }
+
+    let compressed =
+        fs::read(blob_path(&dir, id)).map_err(|e| format!("Error reading snapshot: {}", e))?;
+
+    let mut bytes = Vec::new();
+    GzDecoder::new(compressed.as_slice())
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Error decompressing snapshot: {}", e))?;
+
+    Ok(bytes)
+}
+
+/// Apply `policy` to every file's history under `root`.
+///
+/// Age and per-file count limits are applied first; then, if the store is
+/// still over its total size, the oldest snapshots across all files go.
+pub fn enforce_retention(root: &Path, policy: RetentionPolicy) -> Result<(), String> {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+
+    let entries = match fs::read_dir(root) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(()), // Nothing recorded yet.
+    };
+
+    let now = chrono::Utc::now().timestamp_millis() as u64;
+    let max_age_ms = policy.max_age_days.saturating_mul(24 * 60 * 60 * 1000);
+
+    let mut indexes: Vec<(PathBuf, HistoryIndex)> = Vec::new();
+
+    for entry in entries.flatten() {
+        let dir = entry.path();
+        if !dir.is_dir() {
+            continue;
+        }
+
+        // A directory whose index is missing or damaged is left alone:
+        // treating it as empty would delete every snapshot in it.
+        let mut index = match load_index(&dir) {
+            Ok(Some(index)) => index,
+            Ok(None) => continue,
+            Err(e) => {
+                eprintln!("Skipping history in {}: {}", dir.display(), e);
+                continue;
+            }
+        };
+        let before = index.snapshots.len();
+
+        let mut expired: Vec<Snapshot> = Vec::new();
+        index.snapshots.retain(|s| {
+            let keep = max_age_ms == 0 || now.saturating_sub(s.created_at) <= max_age_ms;
+            if !keep {
+                expired.push(s.clone());
+            }
+            keep
+        });
+
+        if policy.max_versions > 0 && index.snapshots.len() > policy.max_versions {
+            let excess = index.snapshots.len() - policy.max_versions;
+            expired.extend(index.snapshots.drain(..excess));
+        }
+
+        for snapshot in &expired {
+            let _ = fs::remove_file(blob_path(&dir, &snapshot.id));
+        }
+
+        if index.snapshots.len() != before {
+            write_index(&dir, &index)?;
+        }
+
+        indexes.push((dir, index));
+    }
+
+    if policy.max_total_bytes == 0 {
+        return remove_empty(indexes);
+    }
+
+    let mut total: u64 = indexes
+        .iter()
+        .flat_map(|(_, index)| index.snapshots.iter())
+        .map(|s| s.stored_size)
+        .sum();
+
+    // Indexes are written once at the end, however many snapshots go.
+    let mut changed = vec![false; indexes.len()];
+
+    while total > policy.max_total_bytes {
+        // Find the globally oldest snapshot.
+        let oldest = indexes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (_, index))| index.snapshots.first().map(|s| (i, s.created_at)))
+            .min_by_key(|(_, created_at)| *created_at);
+
+        let Some((i, _)) = oldest else {
+            break;
+        };
+
+        let (dir, index) = &mut indexes[i];
+        let snapshot = index.snapshots.remove(0);
+        let _ = fs::remove_file(blob_path(dir, &snapshot.id));
+        changed[i] = true;
+        total = total.saturating_sub(snapshot.stored_size);
+    }
+
+    for ((dir, index), changed) in indexes.iter().zip(changed) {
+        if changed && !index.snapshots.is_empty() {
+            write_index(dir, index)?;
+        }
+    }
+
+    remove_empty(indexes)
+}
+
+/// Delete history directories whose index was read and has no snapshots
+/// left.
+fn remove_empty(indexes: Vec<(PathBuf, HistoryIndex)>) -> Result<(), String> {
+    for (dir, index) in indexes {
+        if index.snapshots.is_empty() {
+            let _ = fs::remove_dir_all(&dir);
+        }
+    }
+    Ok(())
+}
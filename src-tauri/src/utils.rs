@@ -2,10 +2,11 @@ use std::collections::HashSet;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 
+use sha2::{Digest, Sha256};
 use tempfile::NamedTempFile;
 use tokio::task::JoinSet;
 
-use crate::commands::migration::DatabaseV2;
+use crate::commands::migration::DatabaseV4;
 
 /// Maximum file size the app will open (10 MB).
 pub const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
@@ -13,17 +14,96 @@ pub const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
 /// Safely writes to a file, replacing its contents without risk of corruption.
 /// This is a **synchronous** function — call it from `spawn_blocking` or use
 /// the async wrapper [`atomic_write_async`] instead.
-pub fn atomic_write<P: AsRef<Path>>(path: P, content: &str) -> Result<(), std::io::Error> {
-    let parent = path.as_ref().parent().ok_or_else(|| {
-        std::io::Error::new(
-            std::io::ErrorKind::InvalidInput,
-            "path has no parent directory",
-        )
-    })?;
+///
+/// Symlinks are followed so the link itself survives, the replaced file's
+/// permissions (and, on Unix, ownership where allowed) are carried over, and
+/// both the file and its directory are fsynced around the rename.
+pub fn atomic_write<P: AsRef<Path>, C: AsRef<[u8]>>(
+    path: P,
+    content: C,
+) -> Result<(), std::io::Error> {
+    let target = resolve_symlinks(path.as_ref())?;
+
+    let parent = match target.parent() {
+        Some(p) if p.as_os_str().is_empty() => Path::new("."),
+        Some(p) => p,
+        None => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "path has no parent directory",
+            ));
+        }
+    };
 
     let mut temp = NamedTempFile::new_in(parent)?;
-    write!(temp, "{}", content)?;
-    temp.persist(path)?;
+    temp.write_all(content.as_ref())?;
+
+    if let Ok(existing) = std::fs::metadata(&target) {
+        copy_file_metadata(temp.as_file(), &existing)?;
+    }
+
+    temp.as_file().sync_all()?;
+    temp.persist(&target)?;
+    sync_dir(parent)?;
+
+    crate::watcher::note_own_write(&target);
+
+    Ok(())
+}
+
+/// Follow `path` through any symlinks to the file that should actually be
+/// replaced. Dangling links resolve to where they point.
+fn resolve_symlinks(path: &Path) -> Result<PathBuf, std::io::Error> {
+    let is_symlink = std::fs::symlink_metadata(path)
+        .map(|m| m.file_type().is_symlink())
+        .unwrap_or(false);
+
+    if !is_symlink {
+        return Ok(path.to_path_buf());
+    }
+
+    match std::fs::canonicalize(path) {
+        Ok(real) => Ok(real),
+        Err(_) => {
+            let link = std::fs::read_link(path)?;
+            Ok(match path.parent() {
+                Some(parent) if link.is_relative() => parent.join(link),
+                _ => link,
+            })
+        }
+    }
+}
+
+/// Give the temp file the same permissions and ownership as the file it is
+/// about to replace. Ownership changes need privileges we usually don't have,
+/// so failures there are ignored.
+fn copy_file_metadata(
+    temp: &std::fs::File,
+    existing: &std::fs::Metadata,
+) -> Result<(), std::io::Error> {
+    temp.set_permissions(existing.permissions())?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        let _ = std::os::unix::fs::fchown(temp, Some(existing.uid()), Some(existing.gid()));
+    }
+
+    Ok(())
+}
+
+/// Flush a directory entry change (the rename) to disk. Directories can't be
+/// opened for syncing on Windows, where the rename is already durable.
+fn sync_dir(dir: &Path) -> Result<(), std::io::Error> {
+    #[cfg(unix)]
+    {
+        std::fs::File::open(dir)?.sync_all()?;
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = dir;
+    }
 
     Ok(())
 }
@@ -32,12 +112,33 @@ pub fn atomic_write<P: AsRef<Path>>(path: P, content: &str) -> Result<(), std::i
 ///
 /// Moves the owned data into a blocking task so the Tokio runtime thread is
 /// never stalled by disk I/O.
-pub async fn atomic_write_async(path: PathBuf, content: String) -> Result<(), std::io::Error> {
+pub async fn atomic_write_async<C>(path: PathBuf, content: C) -> Result<(), std::io::Error>
+where
+    C: AsRef<[u8]> + Send + 'static,
+{
     tokio::task::spawn_blocking(move || atomic_write(&path, &content))
         .await
         .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?
 }
 
+/// Hex-encoded SHA-256 of `bytes`.
+pub fn content_hash(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Modification time of `metadata` in unix milliseconds (0 when unavailable).
+pub fn modified_millis(metadata: &std::fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
 pub fn sanitize_filename(input: &str) -> String {
     let forbidden = ['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
     let mut s: String = input
@@ -53,15 +154,15 @@ pub fn sanitize_filename(input: &str) -> String {
     s
 }
 
-/// Validate that local files referenced by tabs and recent-files still exist
-/// on disk.  All metadata checks run **concurrently** via a [`JoinSet`] so
-/// startup isn't blocked by slow / network-mounted drives.
-pub async fn validate_local_files(db: &mut DatabaseV2) {
+/// Validate that local files referenced by tabs, recent-files and closed tabs
+/// still exist on disk.  All metadata checks run **concurrently** via a
+/// [`JoinSet`] so startup isn't blocked by slow / network-mounted drives.
+pub async fn validate_local_files(db: &mut DatabaseV4) {
     // 1. Collect every unique path we need to check.
     let mut paths_to_check: Vec<String> = Vec::new();
     let mut seen = HashSet::new();
 
-    for tab in &db.session.tabs {
+    for tab in db.tabs() {
         if let Some(path) = &tab.path {
             if seen.insert(path.clone()) {
                 paths_to_check.push(path.clone());
@@ -69,7 +170,11 @@ pub async fn validate_local_files(db: &mut DatabaseV2) {
         }
     }
 
-    for path in db.recent_files.keys() {
+    for path in db
+        .recent_files
+        .keys()
+        .chain(db.closed_tabs.iter().filter_map(|c| c.tab.path.as_ref()))
+    {
         if seen.insert(path.clone()) {
             paths_to_check.push(path.clone());
         }
@@ -93,25 +198,90 @@ pub async fn validate_local_files(db: &mut DatabaseV2) {
         }
     }
 
-    // 3. Filter tabs: remove local tabs whose files no longer exist.
-    db.session.tabs.retain(|tab| match &tab.path {
+    // 3. Filter recent files and closed tabs. Pinned files are only marked
+    //    missing, so they are still there once their drive is back.
+    db.recent_files.retain(|path, file| {
+        file.missing = !valid_paths.contains(path);
+        file.pinned || !file.missing
+    });
+    db.closed_tabs.retain(|c| match &c.tab.path {
         Some(path) => valid_paths.contains(path),
-        None => true, // untitled tabs are always kept
+        None => true,
     });
 
-    // 4. Filter recent files.
-    db.recent_files.retain(|path, _| valid_paths.contains(path));
+    for session in db.sessions_mut() {
+        // 4. Filter tabs: remove local tabs whose files no longer exist,
+        //    unless they hold unsaved edits the user can still save.
+        session.tabs.retain(|tab| match &tab.path {
+            Some(path) => valid_paths.contains(path) || tab.content.is_some(),
+            None => true, // untitled tabs are always kept
+        });
+
+        // 5. Fix currentTabId if it points to a tab that was just removed.
+        if let Some(current_id) = &session.current_tab_id {
+            let still_exists = session.tabs.iter().any(|tab| &tab.id == current_id);
+            if !still_exists {
+                session.current_tab_id = None;
+            }
+        }
 
-    // 5. Fix currentTabId if it points to a tab that was just removed.
-    if let Some(current_id) = &db.session.current_tab_id {
-        let still_exists = db.session.tabs.iter().any(|tab| &tab.id == current_id);
-        if !still_exists {
-            db.session.current_tab_id = None;
+        // 6. If there is no current tab but tabs remain, select the first one.
+        if session.current_tab_id.is_none() && !session.tabs.is_empty() {
+            session.current_tab_id = Some(session.tabs[0].id.clone());
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_the_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("note.txt");
+
+        atomic_write(&path, "first").unwrap();
+        atomic_write(&path, "second").unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "second");
+        // Only the file itself is left, no temp files.
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn writes_through_a_symlink() {
+        let dir = tempfile::tempdir().unwrap();
+        let real = dir.path().join("real.txt");
+        let link = dir.path().join("link.txt");
+        std::fs::write(&real, "old").unwrap();
+        std::os::unix::fs::symlink("real.txt", &link).unwrap();
+
+        atomic_write(&link, "new").unwrap();
+
+        assert!(
+            std::fs::symlink_metadata(&link)
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+        assert_eq!(std::fs::read_to_string(&real).unwrap(), "new");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_the_permissions_of_the_replaced_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("script.sh");
+        std::fs::write(&path, "old").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o750)).unwrap();
+
+        atomic_write(&path, "new").unwrap();
 
-    // 6. If there is no current tab but tabs remain, select the first one.
-    if db.session.current_tab_id.is_none() && !db.session.tabs.is_empty() {
-        db.session.current_tab_id = Some(db.session.tabs[0].id.clone());
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o750);
     }
 }
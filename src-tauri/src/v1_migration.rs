@@ -0,0 +1,799 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::migration::{DatabaseV4, LocalFile, NoteV1, SESSION_FILE, V1_MANAGER_FILE};
+use crate::note_metadata::{self, NoteMetadata};
+use crate::utils::{self, content_hash, sanitize_filename};
+
+/// Every location the migration reads from or writes to.
+pub struct MigrationPaths {
+    pub v1_manager: PathBuf,
+    pub v1_data_dir: PathBuf,
+    pub v2_data_dir: PathBuf,
+    pub v2_manager: PathBuf,
+    /// Folder the notes are copied into.
+    pub destination: PathBuf,
+    /// The remembered notes home, which is always accepted as a destination.
+    pub notes_home: Option<PathBuf>,
+}
+
+impl MigrationPaths {
+    /// The standard layout: V1 data in the app's local data dir, V2 data in
+    /// its data dir.
+    pub fn new(
+        v1_data_dir: PathBuf,
+        v2_data_dir: PathBuf,
+        destination: PathBuf,
+        notes_home: Option<PathBuf>,
+    ) -> Self {
+        MigrationPaths {
+            v1_manager: v1_data_dir.join(V1_MANAGER_FILE),
+            v2_manager: v2_data_dir.join(SESSION_FILE),
+            v1_data_dir,
+            v2_data_dir,
+            destination,
+            notes_home,
+        }
+    }
+    fn manifest(&self) -> PathBuf {
+        manifest_path(&self.v2_data_dir)
+    }
+
+    fn source_of(&self, note: &NoteV1) -> PathBuf {
+        self.v1_data_dir
+            .join(file_name(&note.tag, &note.file_extension))
+    }
+}
+
+/// The folder to migrate into: `requested` if given (it must be absolute),
+/// otherwise the notes home, otherwise `default`.
+pub fn choose_destination(
+    requested: Option<String>,
+    notes_home: Option<&Path>,
+    default: impl FnOnce() -> Result<PathBuf, String>,
+) -> Result<PathBuf, String> {
+    match (requested, notes_home) {
+        (Some(dir), _) => {
+            let dir = PathBuf::from(dir);
+            if !dir.is_absolute() {
+                return Err(format!(
+                    "Destination must be an absolute path: {}",
+                    dir.display()
+                ));
+            }
+            Ok(dir)
+        }
+        (None, Some(home)) => Ok(home.to_path_buf()),
+        (None, None) => default(),
+    }
+}
+
+/// Check `dir` can take migrated notes without writing to it.
+///
+/// It must be a directory (or not exist yet) and be empty, the notes home, or
+/// hold nothing but plain files, e.g. notes from an earlier migration.
+/// Hidden entries are ignored.
+pub fn check_destination(dir: &Path, notes_home: Option<&Path>) -> Result<(), String> {
+    if notes_home == Some(dir) {
+        return Ok(());
+    }
+
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(format!("Can't read {}: {}", dir.display(), e)),
+    };
+
+    for entry in entries.flatten() {
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        if !entry.file_type().is_ok_and(|t| t.is_file()) {
+            return Err(format!(
+                "{} is not empty and contains {}; choose an empty folder",
+                dir.display(),
+                entry.file_name().to_string_lossy()
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Create `dir` if needed and make sure files can be written to it.
+fn prepare_destination(dir: &Path) -> Result<(), String> {
+    if dir.exists() && !dir.is_dir() {
+        return Err(format!("{} is not a folder", dir.display()));
+    }
+
+    fs::create_dir_all(dir).map_err(|e| format!("Error creating destination folder: {}", e))?;
+
+    tempfile::tempfile_in(dir).map(drop).map_err(|e| {
+        format!(
+            "Destination folder {} is not writable: {}",
+            dir.display(),
+            e
+        )
+    })
+}
+
+/// Where the manifest of the last migration is kept.
+pub fn manifest_path(v2_data_dir: &Path) -> PathBuf {
+    v2_data_dir.join("migration-manifest.json")
+}
+
+/// Record of a completed migration, written before any V1 data is removed.
+/// It holds everything needed to put V1 back.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationManifest {
+    /// Unix milliseconds.
+    pub created_at: u64,
+    pub destination: String,
+    pub v1_manager: String,
+    /// The original V1 manager contents, including notes whose file was missing.
+    pub notes: Vec<NoteV1>,
+    pub files: Vec<MigratedFile>,
+}
+
+/// One V1 note file and where it was copied to.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MigratedFile {
+    pub tag: String,
+    pub source: String,
+    pub destination: String,
+    /// Hex SHA-256 of the content, verified on both sides of the copy.
+    pub checksum: String,
+    /// An identical file was already at `destination`, so nothing was copied
+    /// and rollback must leave it in place.
+    #[serde(default)]
+    pub reused: bool,
+}
+
+/// A V1 note identified for reporting.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteRef {
+    pub tag: String,
+    pub title: String,
+    pub source: String,
+}
+
+impl NoteRef {
+    fn new(note: &NoteV1, source: &Path) -> Self {
+        NoteRef {
+            tag: note.tag.clone(),
+            title: note.title.clone(),
+            source: source.to_string_lossy().to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum PlannedAction {
+    Copy,
+    /// An identical file already exists at the destination.
+    Skip,
+}
+
+/// Where one note will go.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedNote {
+    #[serde(flatten)]
+    pub note: NoteRef,
+    pub destination: String,
+    pub action: PlannedAction,
+}
+
+/// A note whose title-based file name was already taken.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NameConflict {
+    pub tag: String,
+    pub wanted: String,
+    pub resolved: String,
+}
+
+/// What a migration would do, computed without writing anything.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationPlan {
+    pub destination: String,
+    pub mapping: Vec<PlannedNote>,
+    pub conflicts: Vec<NameConflict>,
+    pub missing: Vec<NoteRef>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedNote {
+    #[serde(flatten)]
+    pub note: NoteRef,
+    pub destination: String,
+    pub reason: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FailedNote {
+    #[serde(flatten)]
+    pub note: NoteRef,
+    pub reason: String,
+}
+
+/// Outcome of a migration, note by note.
+///
+/// Failed notes are left untouched in the V1 folder and stay listed in the
+/// V1 manager.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationReport {
+    pub destination: String,
+    pub migrated: Vec<MigratedFile>,
+    pub skipped: Vec<SkippedNote>,
+    pub missing: Vec<NoteRef>,
+    pub failed: Vec<FailedNote>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum NoteOutcome {
+    Migrated,
+    Skipped,
+    Missing,
+    Failed,
+}
+
+/// Emitted after each note is handled.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationProgress {
+    /// 1-based position of this note.
+    pub current: usize,
+    pub total: usize,
+    pub tag: String,
+    pub title: String,
+    pub outcome: NoteOutcome,
+}
+
+pub fn read_v1_notes(v1_manager: &Path) -> Result<Vec<NoteV1>, String> {
+    if !v1_manager.exists() {
+        return Err("No V1 data found to migrate".to_string());
+    }
+
+    let data =
+        fs::read_to_string(v1_manager).map_err(|e| format!("Error reading V1 manager: {}", e))?;
+
+    serde_json::from_str(&data).map_err(|e| format!("Error parsing V1 data: {}", e))
+}
+
+/// `{stem}.{ext}`, or just `stem` when there is no extension.
+fn file_name(stem: &str, ext: &str) -> String {
+    if ext.is_empty() {
+        stem.to_string()
+    } else {
+        format!("{}.{}", stem, ext)
+    }
+}
+
+fn hash_file(path: &Path) -> Result<String, String> {
+    fs::read(path)
+        .map(|bytes| content_hash(&bytes))
+        .map_err(|e| format!("Error reading {}: {}", path.display(), e))
+}
+
+/// Where a note goes and whether it needs copying.
+struct Target {
+    path: PathBuf,
+    action: PlannedAction,
+    /// The title-based name, when a different one had to be picked.
+    wanted: Option<PathBuf>,
+}
+
+/// Pick the destination for a note: the first of `Title.ext`, `Title (2).ext`,
+/// ... that is neither handed out earlier in this run nor occupied by a
+/// different file. A candidate already holding identical content is reused.
+fn resolve_target(
+    dir: &Path,
+    stem: &str,
+    ext: &str,
+    checksum: &str,
+    taken: &mut HashSet<PathBuf>,
+) -> Target {
+    let wanted = dir.join(file_name(stem, ext));
+    let mut n = 1;
+
+    loop {
+        let candidate = if n == 1 {
+            wanted.clone()
+        } else {
+            dir.join(file_name(&format!("{} ({})", stem, n), ext))
+        };
+        n += 1;
+
+        if taken.contains(&candidate) {
+            continue;
+        }
+
+        let action = if !candidate.exists() {
+            PlannedAction::Copy
+        } else if hash_file(&candidate).is_ok_and(|h| h == checksum) {
+            PlannedAction::Skip
+        } else {
+            continue;
+        };
+
+        taken.insert(candidate.clone());
+        return Target {
+            wanted: (candidate != wanted).then_some(wanted),
+            path: candidate,
+            action,
+        };
+    }
+}
+
+/// Copy `src` to `dest` and check the copy hashes to `checksum`. On mismatch
+/// the copy is removed.
+fn copy_verified(src: &Path, dest: &Path, checksum: &str) -> Result<(), String> {
+    fs::copy(src, dest).map_err(|e| {
+        format!(
+            "Failed to copy file {} -> {}: {}",
+            src.display(),
+            dest.display(),
+            e
+        )
+    })?;
+
+    if hash_file(dest)? != checksum {
+        let _ = fs::remove_file(dest);
+        return Err(format!(
+            "Checksum mismatch after copying {} -> {}",
+            src.display(),
+            dest.display()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Work out where every V1 note would go, without writing anything.
+pub fn plan(paths: &MigrationPaths) -> Result<MigrationPlan, String> {
+    let notes = read_v1_notes(&paths.v1_manager)?;
+
+    if paths.destination.exists() && !paths.destination.is_dir() {
+        return Err(format!("{} is not a folder", paths.destination.display()));
+    }
+    check_destination(&paths.destination, paths.notes_home.as_deref())?;
+
+    let mut taken = HashSet::new();
+    let mut plan = MigrationPlan {
+        destination: paths.destination.to_string_lossy().to_string(),
+        mapping: Vec::new(),
+        conflicts: Vec::new(),
+        missing: Vec::new(),
+    };
+
+    for note in &notes {
+        let src = paths.source_of(note);
+        let Ok(checksum) = hash_file(&src) else {
+            plan.missing.push(NoteRef::new(note, &src));
+            continue;
+        };
+
+        let target = resolve_target(
+            &paths.destination,
+            &sanitize_filename(&note.title),
+            &note.file_extension,
+            &checksum,
+            &mut taken,
+        );
+
+        let destination = target.path.to_string_lossy().to_string();
+        if let Some(wanted) = target.wanted {
+            plan.conflicts.push(NameConflict {
+                tag: note.tag.clone(),
+                wanted: wanted.to_string_lossy().to_string(),
+                resolved: destination.clone(),
+            });
+        }
+
+        plan.mapping.push(PlannedNote {
+            note: NoteRef::new(note, &src),
+            destination,
+            action: target.action,
+        });
+    }
+
+    Ok(plan)
+}
+
+/// Copy every V1 note into `paths.destination`, write the V2 session and the
+/// manifest, then remove the V1 files that were migrated.
+///
+/// A note that fails to copy is reported and left in V1; the rest carry on.
+/// V1 data is only touched once the manifest is on disk; if writing it, the
+/// session or the note metadata fails, the copies made so far and whatever
+/// was written are removed. `on_progress` is called after each note.
+/// Blocking; run it on a blocking thread.
+///
+/// Refused once a V2 session exists: it would be replaced. Notes that failed
+/// then are copied with [`retry_failed`] instead.
+pub fn migrate(
+    paths: &MigrationPaths,
+    on_progress: impl FnMut(MigrationProgress),
+) -> Result<MigrationReport, String> {
+    if paths.v2_manager.exists() {
+        return Err(
+            "Notes were already migrated; only the notes that couldn't be copied can be retried"
+                .to_string(),
+        );
+    }
+
+    let notes = read_v1_notes(&paths.v1_manager)?;
+
+    check_destination(&paths.destination, paths.notes_home.as_deref())?;
+    prepare_destination(&paths.destination)?;
+    fs::create_dir_all(&paths.v2_data_dir)
+        .map_err(|e| format!("Error creating V2 data dir: {}", e))?;
+
+    let copied = copy_notes(paths, &notes, on_progress);
+
+    let manifest = MigrationManifest {
+        created_at: chrono::Utc::now().timestamp_millis() as u64,
+        destination: copied.report.destination.clone(),
+        v1_manager: paths.v1_manager.to_string_lossy().to_string(),
+        notes,
+        files: copied.files,
+    };
+
+    // The metadata goes last: the store is shared with earlier notes, so it
+    // is only written once nothing else can fail.
+    let result = write_json(&paths.manifest(), &manifest)
+        .and_then(|_| {
+            let db = DatabaseV4 {
+                recent_files: copied.recent_files,
+                ..DatabaseV4::default()
+            };
+            write_json(&paths.v2_manager, &db)
+        })
+        .and_then(|_| note_metadata::write_store(&copied.metadata_path, &copied.metadata));
+
+    if let Err(e) = result {
+        remove_copies(&manifest.files);
+        let _ = fs::remove_file(&paths.v2_manager);
+        let _ = fs::remove_file(paths.manifest());
+        return Err(e);
+    }
+
+    remove_v1_data(paths, &manifest.files, &manifest.notes, &copied.failed_tags);
+
+    Ok(copied.report)
+}
+
+/// The V1 notes an earlier migration couldn't copy. They stay listed in the
+/// V1 manager until a retry copies them; empty when no migration has run.
+pub fn failed_notes(paths: &MigrationPaths) -> Vec<NoteRef> {
+    if !paths.manifest().exists() {
+        return Vec::new();
+    }
+
+    read_v1_notes(&paths.v1_manager)
+        .unwrap_or_default()
+        .iter()
+        .map(|note| NoteRef::new(note, &paths.source_of(note)))
+        .collect()
+}
+
+/// Copy the notes an earlier migration couldn't into its destination, add
+/// them to its manifest so a rollback covers them too, then remove their V1
+/// files. Notes that fail again stay in V1 for another retry.
+///
+/// The session isn't written: it is live by now, so the recent files of the
+/// copied notes are returned for the caller to add. Blocking; run it on a
+/// blocking thread.
+pub fn retry_failed(
+    paths: &MigrationPaths,
+    on_progress: impl FnMut(MigrationProgress),
+) -> Result<(MigrationReport, HashMap<String, LocalFile>), String> {
+    let notes = read_v1_notes(&paths.v1_manager)?;
+    let mut manifest = read_manifest(&paths.v2_data_dir)?;
+
+    prepare_destination(&paths.destination)?;
+
+    let copied = copy_notes(paths, &notes, on_progress);
+    let copied_files = copied.files.len();
+    manifest.files.extend(copied.files);
+
+    let result = write_json(&paths.manifest(), &manifest)
+        .and_then(|_| note_metadata::write_store(&copied.metadata_path, &copied.metadata));
+
+    let earlier_files = manifest.files.len() - copied_files;
+    if let Err(e) = result {
+        remove_copies(&manifest.files[earlier_files..]);
+        manifest.files.truncate(earlier_files);
+        if let Err(e) = write_json(&paths.manifest(), &manifest) {
+            eprintln!("Warning: Could not restore the migration manifest: {}", e);
+        }
+        return Err(e);
+    }
+
+    let new_files = &manifest.files[earlier_files..];
+    remove_v1_data(paths, new_files, &notes, &copied.failed_tags);
+
+    Ok((copied.report, copied.recent_files))
+}
+
+/// What [`copy_notes`] did, for the caller to record.
+struct Copied {
+    report: MigrationReport,
+    files: Vec<MigratedFile>,
+    recent_files: HashMap<String, LocalFile>,
+    failed_tags: HashSet<String>,
+    metadata_path: PathBuf,
+    /// The metadata store with the copied notes added.
+    metadata: note_metadata::MetadataStore,
+}
+
+/// Copy each of `notes` into `paths.destination`. A note that fails is
+/// reported and the rest carry on.
+fn copy_notes(
+    paths: &MigrationPaths,
+    notes: &[NoteV1],
+    mut on_progress: impl FnMut(MigrationProgress),
+) -> Copied {
+    let mut taken = HashSet::new();
+    let mut files: Vec<MigratedFile> = Vec::new();
+    let mut recent_files: HashMap<String, LocalFile> = HashMap::new();
+    let mut report = MigrationReport {
+        destination: paths.destination.to_string_lossy().to_string(),
+        migrated: Vec::new(),
+        skipped: Vec::new(),
+        missing: Vec::new(),
+        failed: Vec::new(),
+    };
+    let mut failed_tags = HashSet::new();
+    let metadata_path = note_metadata::metadata_path(&paths.v2_data_dir);
+    let mut metadata = note_metadata::read_store(&metadata_path);
+
+    for (i, note) in notes.iter().enumerate() {
+        let src = paths.source_of(note);
+        let outcome = match migrate_note(paths, note, &src, &mut taken) {
+            Ok(None) => {
+                report.missing.push(NoteRef::new(note, &src));
+                NoteOutcome::Missing
+            }
+            Ok(Some(file)) => {
+                let dest = PathBuf::from(&file.destination);
+                let modified = fs::metadata(&dest)
+                    .map(|m| utils::modified_millis(&m))
+                    .unwrap_or(0);
+                let filename = dest
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+
+                metadata.insert(file.destination.clone(), NoteMetadata::from(note));
+                recent_files.insert(
+                    file.destination.clone(),
+                    LocalFile {
+                        id: note.tag.clone(),
+                        filename,
+                        path: file.destination.clone(),
+                        modified,
+                        open_count: 0,
+                        last_opened: 0,
+                        pinned: false,
+                        missing: false,
+                    },
+                );
+
+                let outcome = if file.reused {
+                    report.skipped.push(SkippedNote {
+                        note: NoteRef::new(note, &src),
+                        destination: file.destination.clone(),
+                        reason: "An identical file already exists at the destination".to_string(),
+                    });
+                    NoteOutcome::Skipped
+                } else {
+                    report.migrated.push(file.clone());
+                    NoteOutcome::Migrated
+                };
+
+                files.push(file);
+                outcome
+            }
+            Err(reason) => {
+                failed_tags.insert(note.tag.clone());
+                report.failed.push(FailedNote {
+                    note: NoteRef::new(note, &src),
+                    reason,
+                });
+                NoteOutcome::Failed
+            }
+        };
+
+        on_progress(MigrationProgress {
+            current: i + 1,
+            total: notes.len(),
+            tag: note.tag.clone(),
+            title: note.title.clone(),
+            outcome,
+        });
+    }
+
+    Copied {
+        report,
+        files,
+        recent_files,
+        failed_tags,
+        metadata_path,
+        metadata,
+    }
+}
+
+/// Remove the V1 files of `files`, once they are recorded in the manifest,
+/// and leave only the failed ones of `notes` listed in the V1 manager.
+fn remove_v1_data(
+    paths: &MigrationPaths,
+    files: &[MigratedFile],
+    notes: &[NoteV1],
+    failed_tags: &HashSet<String>,
+) {
+    // Clean up only the V1 files we migrated; the V1 and V2 data dirs can be
+    // the same directory on some platforms.
+    for file in files {
+        if let Err(e) = fs::remove_file(&file.source) {
+            eprintln!("Warning: Could not remove V1 file {}: {}", file.source, e);
+        }
+    }
+
+    // Keep failed notes listed in V1 so they aren't orphaned, and can be
+    // retried.
+    let cleanup = if failed_tags.is_empty() {
+        fs::remove_file(&paths.v1_manager).map_err(|e| e.to_string())
+    } else {
+        let remaining: Vec<&NoteV1> = notes
+            .iter()
+            .filter(|n| failed_tags.contains(&n.tag))
+            .collect();
+        write_json(&paths.v1_manager, &remaining)
+    };
+    if let Err(e) = cleanup {
+        eprintln!("Warning: Could not clean up V1 manager: {}", e);
+    }
+}
+
+/// Copy one note. `Ok(None)` means its V1 file is missing.
+fn migrate_note(
+    paths: &MigrationPaths,
+    note: &NoteV1,
+    src: &Path,
+    taken: &mut HashSet<PathBuf>,
+) -> Result<Option<MigratedFile>, String> {
+    if !src.exists() {
+        return Ok(None);
+    }
+
+    let checksum = hash_file(src)?;
+    let target = resolve_target(
+        &paths.destination,
+        &sanitize_filename(&note.title),
+        &note.file_extension,
+        &checksum,
+        taken,
+    );
+
+    let reused = target.action == PlannedAction::Skip;
+    if !reused {
+        copy_verified(src, &target.path, &checksum)?;
+
+        if let Err(e) = set_modified(&target.path, note.updated_at) {
+            eprintln!(
+                "Warning: Could not set modification time of {}: {}",
+                target.path.display(),
+                e
+            );
+        }
+    }
+
+    Ok(Some(MigratedFile {
+        tag: note.tag.clone(),
+        source: src.to_string_lossy().to_string(),
+        destination: target.path.to_string_lossy().to_string(),
+        checksum,
+        reused,
+    }))
+}
+
+/// Set the mtime of `path` to a V1 timestamp (unix milliseconds).
+fn set_modified(path: &Path, millis: u64) -> std::io::Result<()> {
+    fs::File::options()
+        .write(true)
+        .open(path)?
+        .set_modified(UNIX_EPOCH + Duration::from_millis(millis))
+}
+
+fn remove_copies(files: &[MigratedFile]) {
+    for file in files.iter().filter(|f| !f.reused) {
+        let _ = fs::remove_file(&file.destination);
+    }
+}
+
+fn write_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), String> {
+    let serialized =
+        serde_json::to_string_pretty(value).map_err(|e| format!("Serialization error: {}", e))?;
+    utils::atomic_write(path, serialized)
+        .map_err(|e| format!("Error writing {}: {}", path.display(), e))
+}
+
+pub fn read_manifest(v2_data_dir: &Path) -> Result<MigrationManifest, String> {
+    let path = manifest_path(v2_data_dir);
+    let raw = fs::read_to_string(&path)
+        .map_err(|e| format!("No migration to roll back ({}): {}", path.display(), e))?;
+    serde_json::from_str(&raw).map_err(|e| format!("Error parsing migration manifest: {}", e))
+}
+
+/// Undo a migration using its manifest.
+///
+/// Each migrated file is moved back to its V1 location (keeping any edits
+/// made since), the V1 manager is rewritten from the manifest and the V2
+/// session is moved aside so the migration is offered again on next start.
+/// Returns how many notes were restored.
+pub fn rollback(v2_data_dir: &Path, v2_manager: &Path) -> Result<usize, String> {
+    let manifest = read_manifest(v2_data_dir)?;
+    let mut restored = 0;
+
+    for file in &manifest.files {
+        let source = Path::new(&file.source);
+        let destination = Path::new(&file.destination);
+
+        if !destination.exists() {
+            if source.exists() {
+                restored += 1;
+            } else {
+                eprintln!("Warning: {} is gone, can't restore it", file.destination);
+            }
+            continue;
+        }
+
+        if let Some(parent) = source.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Error creating {}: {}", parent.display(), e))?;
+        }
+
+        copy_verified(destination, source, &hash_file(destination)?)?;
+        if !file.reused {
+            let _ = fs::remove_file(destination);
+        }
+        restored += 1;
+    }
+
+    write_json(Path::new(&manifest.v1_manager), &manifest.notes)?;
+
+    let metadata_path = note_metadata::metadata_path(v2_data_dir);
+    let mut metadata = note_metadata::read_store(&metadata_path);
+    for file in manifest.files.iter().filter(|f| !f.reused) {
+        metadata.remove(&file.destination);
+    }
+    note_metadata::write_store(&metadata_path, &metadata)?;
+
+    if v2_manager.exists() {
+        let aside = v2_manager.with_file_name(format!(
+            "session.rolled-back-{}.json",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        fs::rename(v2_manager, &aside)
+            .map_err(|e| format!("Error moving V2 session aside: {}", e))?;
+    }
+
+    let _ = fs::remove_file(manifest_path(v2_data_dir));
+
+    Ok(restored)
+}
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+use crate::handoff;
+
+/// Flags handing a [`WaitChannel`] to the instance that opens the files.
+pub(crate) const WAIT_PORT_FLAG: &str = "--wait-port=";
+pub(crate) const WAIT_TOKEN_FLAG: &str = "--wait-token-file=";
+
+/// Where a waiting process listens for the signal that its tabs are done.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WaitChannel {
+    pub port: u16,
+    /// Sent back with the signal so no other local process can end the wait.
+    pub token: String,
+}
+
+impl WaitChannel {
+    /// Arguments handing this channel to the instance that opens the files.
+    /// The token goes through a [`handoff`] file, as anyone can read a
+    /// process's arguments; only the file's name is passed.
+    fn to_args(&self) -> std::io::Result<[String; 2]> {
+        let token_file = handoff::write(self.token.as_bytes())?;
+        Ok([
+            format!("{}{}", WAIT_PORT_FLAG, self.port),
+            format!("{}{}", WAIT_TOKEN_FLAG, token_file),
+        ])
+    }
+
+    /// The channel passed as `port` and `token_file` by [`to_args`]. The
+    /// token file is consumed.
+    ///
+    /// [`to_args`]: WaitChannel::to_args
+    pub fn from_args(port: u16, token_file: &str) -> std::io::Result<Self> {
+        let token = String::from_utf8(handoff::take(token_file)?)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        Ok(WaitChannel { port, token })
+    }
+
+    fn notify(&self) -> std::io::Result<()> {
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, self.port))?;
+        stream.write_all(self.token.as_bytes())?;
+        stream.write_all(b"\n")
+    }
+}
+
+/// Run as the `--wait` side of the command line and never return.
+///
+/// The process that opens the files can't block: if another instance is
+/// running, the single-instance plugin exits it right after forwarding its
+/// arguments. So this process starts a copy of itself without `--wait`,
+/// passing a loopback port to report back on, and exits once the instance
+/// showing the files reports that they were saved or closed.
+pub fn run_waiter(args: &[String]) -> ! {
+    let listener = match TcpListener::bind((Ipv4Addr::LOCALHOST, 0)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Error starting --wait listener: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let channel = WaitChannel {
+        port: listener.local_addr().map(|a| a.port()).unwrap_or_default(),
+        token: uuid::Uuid::new_v4().to_string(),
+    };
+
+    let channel_args = match channel.to_args() {
+        Ok(channel_args) => channel_args,
+        Err(e) => {
+            eprintln!("Error handing over the --wait token: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let child_args = args
+        .iter()
+        .filter(|a| *a != "--wait")
+        .cloned()
+        .chain(channel_args);
+
+    let spawned = std::env::current_exe().and_then(|exe| {
+        Command::new(exe)
+            .args(child_args)
+            .stdin(Stdio::inherit())
+            .spawn()
+    });
+
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(e) => {
+            eprintln!("Error starting the editor: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // A child that fails before opening anything will never report back.
+    std::thread::spawn(move || {
+        if let Ok(status) = child.wait()
+            && !status.success()
+        {
+            std::process::exit(status.code().unwrap_or(1));
+        }
+    });
+
+    for stream in listener.incoming().flatten() {
+        let mut line = String::new();
+        if BufReader::new(stream).read_line(&mut line).is_ok() && line.trim() == channel.token {
+            std::process::exit(0);
+        }
+    }
+
+    std::process::exit(1);
+}
+
+/// Channels of the `--wait` invocations whose tabs are still open, by wait id.
+#[derive(Default)]
+pub struct Waiters(Mutex<HashMap<String, WaitChannel>>);
+
+impl Waiters {
+    /// Start tracking `channel` and return the wait id the frontend will
+    /// report back with. The id is not the token, which stays in the backend.
+    pub fn register(&self, channel: WaitChannel) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+        self.0.lock().unwrap().insert(id.clone(), channel);
+        id
+    }
+
+    /// Release the process waiting on `id`, if it is still waiting.
+    pub fn finish(&self, id: &str) {
+        let channel = self.0.lock().unwrap().remove(id);
+
+        if let Some(channel) = channel
+            && let Err(e) = channel.notify()
+        {
+            eprintln!("Warning: could not release waiting process: {}", e);
+        }
+    }
+
+    /// Release every waiting process. Called when the app exits.
+    pub fn finish_all(&self) {
+        let channels: Vec<WaitChannel> = self.0.lock().unwrap().drain().map(|(_, c)| c).collect();
+
+        for channel in channels {
+            let _ = channel.notify();
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use notify_debouncer_full::notify::event::{ModifyKind, RenameMode};
+use notify_debouncer_full::notify::{EventKind, RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{
+    DebounceEventResult, DebouncedEvent, Debouncer, RecommendedCache, new_debouncer,
+};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+/// How long the debouncer waits for a burst of events to settle.
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Events for a path we wrote ourselves within this window are ignored.
+const OWN_WRITE_WINDOW: Duration = Duration::from_secs(2);
+
+/// Paths recently written by [`atomic_write`](crate::utils::atomic_write),
+/// keyed by canonical path.
+static OWN_WRITES: LazyLock<Mutex<HashMap<PathBuf, Instant>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Record that the app itself just wrote `path`, so the watcher doesn't
+/// report the change back to the frontend as an external modification.
+pub fn note_own_write(path: &Path) {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut writes = OWN_WRITES.lock().unwrap();
+    writes.retain(|_, at| at.elapsed() < OWN_WRITE_WINDOW);
+    writes.insert(path, Instant::now());
+}
+
+fn is_own_write(path: &Path) -> bool {
+    OWN_WRITES
+        .lock()
+        .unwrap()
+        .get(path)
+        .is_some_and(|at| at.elapsed() < OWN_WRITE_WINDOW)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileChanged {
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileRenamed {
+    pub from: String,
+    pub to: String,
+}
+
+/// Watches the files open in tabs and emits `file-changed`, `file-deleted`
+/// and `file-renamed` events to the webview.
+///
+/// Parent directories are watched rather than the files themselves, so a file
+/// replaced by another editor's rename-over-save is still tracked.
+pub struct FileWatcher {
+    debouncer: Mutex<Option<Debouncer<RecommendedWatcher, RecommendedCache>>>,
+    /// Canonical path -> path as the frontend knows it.
+    files: Arc<Mutex<HashMap<PathBuf, String>>>,
+    dirs: Mutex<HashSet<PathBuf>>,
+    /// Paths open in each window, by window label.
+    windows: Mutex<HashMap<String, Vec<String>>>,
+}
+
+impl FileWatcher {
+    pub fn new(app: AppHandle) -> Self {
+        let files: Arc<Mutex<HashMap<PathBuf, String>>> = Arc::default();
+        let handler_files = files.clone();
+
+        let debouncer = new_debouncer(
+            DEBOUNCE_TIMEOUT,
+            None,
+            move |result: DebounceEventResult| match result {
+                Ok(events) => {
+                    for event in events {
+                        handle_event(&app, &handler_files, &event);
+                    }
+                }
+                Err(errors) => {
+                    for e in errors {
+                        eprintln!("File watcher error: {}", e);
+                    }
+                }
+            },
+        );
+
+        let debouncer = match debouncer {
+            Ok(d) => Some(d),
+            Err(e) => {
+                eprintln!("Could not start file watcher: {}", e);
+                None
+            }
+        };
+
+        FileWatcher {
+            debouncer: Mutex::new(debouncer),
+            files,
+            dirs: Mutex::new(HashSet::new()),
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Replace the files watched for window `label` with `paths`. Files open
+    /// in any window are watched.
+    pub fn watch_window_paths(&self, label: &str, paths: Vec<String>) {
+        let mut windows = self.windows.lock().unwrap();
+        windows.insert(label.to_string(), paths);
+        self.watch_paths(windows.values().flatten().map(String::as_str));
+    }
+
+    /// Stop watching the files of a window that was closed.
+    pub fn forget_window(&self, label: &str) {
+        let mut windows = self.windows.lock().unwrap();
+        if windows.remove(label).is_some() {
+            self.watch_paths(windows.values().flatten().map(String::as_str));
+        }
+    }
+
+    /// Replace the set of watched files with `paths`.
+    fn watch_paths<'a>(&self, paths: impl IntoIterator<Item = &'a str>) {
+        let mut files = HashMap::new();
+        for path in paths {
+            let original = Path::new(path);
+            let canonical = std::fs::canonicalize(original).unwrap_or_else(|_| original.into());
+            files.insert(canonical, path.to_string());
+        }
+
+        let wanted_dirs: HashSet<PathBuf> = files
+            .keys()
+            .filter_map(|p| p.parent().map(Path::to_path_buf))
+            .collect();
+
+        *self.files.lock().unwrap() = files;
+
+        let mut guard = self.debouncer.lock().unwrap();
+        let Some(debouncer) = guard.as_mut() else {
+            return;
+        };
+
+        let mut dirs = self.dirs.lock().unwrap();
+
+        for dir in dirs.difference(&wanted_dirs) {
+            let _ = debouncer.unwatch(dir);
+        }
+
+        let mut watched = HashSet::new();
+        for dir in wanted_dirs {
+            if dirs.contains(&dir) || debouncer.watch(&dir, RecursiveMode::NonRecursive).is_ok() {
+                watched.insert(dir);
+            }
+        }
+
+        *dirs = watched;
+    }
+}
+
+fn handle_event(app: &AppHandle, files: &Mutex<HashMap<PathBuf, String>>, event: &DebouncedEvent) {
+    let mut files = files.lock().unwrap();
+
+    match event.kind {
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+            let (from, to) = (&event.paths[0], &event.paths[1]);
+
+            if let Some(original) = files.remove(from) {
+                let to_string = to.to_string_lossy().to_string();
+                files.insert(to.clone(), to_string.clone());
+                let _ = app.emit(
+                    "file-renamed",
+                    FileRenamed {
+                        from: original,
+                        to: to_string,
+                    },
+                );
+            } else if let Some(original) = files.get(to) {
+                // Another program saved by renaming a temp file over ours.
+                if !is_own_write(to) {
+                    let _ = app.emit(
+                        "file-changed",
+                        FileChanged {
+                            path: original.clone(),
+                        },
+                    );
+                }
+            }
+        }
+        EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+            for path in &event.paths {
+                if let Some(original) = files.get(path) {
+                    if path.exists() {
+                        continue;
+                    }
+                    let _ = app.emit(
+                        "file-deleted",
+                        FileChanged {
+                            path: original.clone(),
+                        },
+                    );
+                }
+            }
+        }
+        EventKind::Create(_)
+        | EventKind::Modify(
+            ModifyKind::Data(_) | ModifyKind::Any | ModifyKind::Name(RenameMode::To),
+        ) => {
+            for path in &event.paths {
+                if let Some(original) = files.get(path) {
+                    if is_own_write(path) {
+                        continue;
+                    }
+                    let _ = app.emit(
+                        "file-changed",
+                        FileChanged {
+                            path: original.clone(),
+                        },
+                    );
+                }
+            }
+        }
+        _ => {}
+    }
+}
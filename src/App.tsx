@@ -10,6 +10,7 @@ import Footer from "@/components/footer";
 import ShortcutProvider from "@/components/shortcut-provider";
 import FileDropZone from "@/components/file-drop-zone";
 import ExternalFileListener from "@/components/external-file-listener";
+import FileWatchListener from "@/components/file-watch-listener";
 import { useMigrationStatus } from "@/stores/migration";
 
 export default function RootLayout() {
@@ -37,6 +38,7 @@ export default function RootLayout() {
       <ShortcutProvider />
       <FileDropZone />
       <ExternalFileListener />
+      <FileWatchListener />
     </div>
   );
 }
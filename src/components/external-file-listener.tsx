@@ -1,7 +1,8 @@
 import { useEffect } from "react";
-import { listen } from "@tauri-apps/api/event";
+import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
 import { useEditorActions } from "@/stores/editor";
 import { useNavigate } from "react-router";
+import { OpenRequest } from "@/lib/types";
 
 /**
  * Invisible component that listens for the `open-files` event emitted by
@@ -10,20 +11,20 @@ import { useNavigate } from "react-router";
  * Taking Notes" while the app is already running).
  *
  * The single-instance plugin prevents the second instance from starting
- * and forwards its CLI arguments to the running instance via this event.
+ * and forwards its CLI arguments to the running instance via this event,
+ * addressed to the window that last had focus. Tabs moved here from another
+ * window arrive the same way.
  */
 export default function ExternalFileListener() {
-  const { openByPath } = useEditorActions();
+  const { openRequests } = useEditorActions();
   const navigate = useNavigate();
 
   useEffect(() => {
-    const unlisten = listen<string[]>("open-files", async (event) => {
-      const paths = event.payload;
-      if (paths.length === 0) return;
+    const unlisten = getCurrentWebviewWindow().listen<OpenRequest[]>("open-files", async (event) => {
+      const requests = event.payload;
+      if (requests.length === 0) return;
 
-      for (const path of paths) {
-        await openByPath(path);
-      }
+      await openRequests(requests);
 
       // Navigate to the editor so the newly opened file is visible.
       navigate("/");
@@ -0,0 +1,27 @@
+import { useEffect } from "react";
+import { listen } from "@tauri-apps/api/event";
+import { useEditorActions } from "@/stores/editor";
+import { FileChangedEvent, FileRenamedEvent } from "@/lib/types";
+
+/**
+ * Invisible component that listens for the events the Rust file watcher
+ * emits when another program changes, deletes or renames a file open in a
+ * tab. They go to every window; each one only acts on its own tabs.
+ */
+export default function FileWatchListener() {
+  const { fileChangedOnDisk, fileDeletedOnDisk, fileRenamedOnDisk } = useEditorActions();
+
+  useEffect(() => {
+    const unlisteners = [
+      listen<FileChangedEvent>("file-changed", (event) => fileChangedOnDisk(event.payload.path)),
+      listen<FileChangedEvent>("file-deleted", (event) => fileDeletedOnDisk(event.payload.path)),
+      listen<FileRenamedEvent>("file-renamed", (event) => fileRenamedOnDisk(event.payload.from, event.payload.to)),
+    ];
+
+    return () => {
+      unlisteners.forEach((unlisten) => unlisten.then((fn) => fn()));
+    };
+  }, []);
+
+  return null;
+}
@@ -1,7 +1,9 @@
 import React, { useEffect, useRef, useState } from "react";
 import { useNavigate } from "react-router";
-import { useEditorActions } from "@/stores/editor";
+import { useCurrentTabMeta, useEditorActions } from "@/stores/editor";
 import { message } from "@tauri-apps/plugin-dialog";
+import { getAllWebviewWindows, getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
+import { newWindow } from "@/lib/commands";
 import clsx from "clsx";
 
 type DropdownItem = {
@@ -115,8 +117,37 @@ const InlineButton: React.FC<{
   );
 };
 
+/**
+ * Labels of every open window, the main one first and the rest in the order
+ * they were opened. Refreshed whenever this window gains focus.
+ */
+function useWindowLabels() {
+  const [labels, setLabels] = useState<string[]>([]);
+
+  useEffect(() => {
+    const current = getCurrentWebviewWindow();
+    const refresh = () =>
+      getAllWebviewWindows()
+        .then((windows) => setLabels(windows.map((w) => w.label).sort((a, b) => (a === "main" ? -1 : b === "main" ? 1 : a.localeCompare(b)))))
+        .catch((error) => console.error("Failed to list windows:", error));
+
+    refresh();
+    const unlisten = current.onFocusChanged(({ payload: focused }) => {
+      if (focused) refresh();
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  return labels;
+}
+
 export default function MenuBar() {
-  const { addBlank, openLocalFile, saveCurrentFileOnDisk } = useEditorActions();
+  const { addBlank, openLocalFile, saveCurrentFileOnDisk, moveTab, restoreClosedTab } = useEditorActions();
+  const currentTab = useCurrentTabMeta();
+  const windowLabels = useWindowLabels();
   const navigate = useNavigate();
 
   const fileItems: DropdownItem[] = [
@@ -138,6 +169,21 @@ export default function MenuBar() {
       onClick: () => navigate("/recent-files"),
       available: true,
     },
+    {
+      id: "reopen-closed-tab",
+      text: "Reopen Closed Tab",
+      shortcut: "Ctrl+Shift+T",
+      onClick: async () => {
+        if (await restoreClosedTab()) navigate("/");
+      },
+      available: true,
+    },
+    {
+      id: "recently-closed",
+      text: "Recently Closed",
+      onClick: () => navigate("/closed-tabs"),
+      available: true,
+    },
     { id: "save", text: "Save", shortcut: "Ctrl+S", onClick: saveCurrentFileOnDisk, available: true },
   ];
 
@@ -176,6 +222,43 @@ export default function MenuBar() {
     { id: "zoom-out", text: "Zoom Out", onClick: () => window.dispatchEvent(new CustomEvent("zoom-out")), available: false },
   ];
 
+  const currentLabel = getCurrentWebviewWindow().label;
+  const windowItems: DropdownItem[] = [
+    {
+      id: "workspaces",
+      text: "Workspaces",
+      onClick: () => navigate("/workspaces"),
+      available: true,
+    },
+    {
+      id: "new-window",
+      text: "New Window",
+      shortcut: "Ctrl+Shift+N",
+      onClick: () => {
+        newWindow().catch((error) => console.error("Failed to open window:", error));
+      },
+      available: true,
+    },
+    {
+      id: "move-tab-new-window",
+      text: "Move Tab to New Window",
+      onClick: () => currentTab && moveTab(currentTab.id),
+      available: currentTab !== null,
+    },
+    ...windowLabels.flatMap((label, index) =>
+      label === currentLabel
+        ? []
+        : [
+            {
+              id: `move-tab-${label}`,
+              text: `Move Tab to Window ${index + 1}`,
+              onClick: () => currentTab && moveTab(currentTab.id, label),
+              available: currentTab !== null,
+            },
+          ],
+    ),
+  ];
+
   const helpItems: DropdownItem[] = [
     {
       id: "docs",
@@ -211,6 +294,7 @@ export default function MenuBar() {
       <Menu text="file" items={fileItems} id="menu-file" />
       <Menu text="edit" items={editItems} id="menu-edit" />
       <Menu text="view" items={viewItems} id="menu-view" />
+      <Menu text="window" items={windowItems} id="menu-window" />
       <Menu text="help" items={helpItems} id="menu-help" />
 
       {/* Spacer */}
@@ -1,6 +1,7 @@
 import { useEffect } from "react";
 import { useNavigate } from "react-router";
 import { useEditorActions } from "@/stores/editor";
+import { newWindow } from "@/lib/commands";
 
 function normalizeShortcut(e: KeyboardEvent) {
   const keys = [];
@@ -16,7 +17,7 @@ function normalizeShortcut(e: KeyboardEvent) {
 }
 
 export default function GlobalShortcutProvider() {
-  const { addBlank, openLocalFile, saveCurrentFileOnDisk, closeCurrentTab } = useEditorActions();
+  const { addBlank, openLocalFile, saveCurrentFileOnDisk, closeCurrentTab, restoreClosedTab } = useEditorActions();
   const navigate = useNavigate();
 
   /**
@@ -25,6 +26,8 @@ export default function GlobalShortcutProvider() {
    * - Ctrl+O: Open a local file
    * - Ctrl+S: Save the current file on disk
    * - Ctrl+W: Close the current tab
+   * - Ctrl+Shift+T: Reopen the last closed tab
+   * - Ctrl+Shift+N: Open a new window
    */
   const shortcuts: Record<string, () => void> = {
     "ctrl+t": () => {
@@ -34,6 +37,12 @@ export default function GlobalShortcutProvider() {
     "ctrl+o": () => openLocalFile(),
     "ctrl+s": () => saveCurrentFileOnDisk(),
     "ctrl+w": () => closeCurrentTab(),
+    "ctrl+shift+t": async () => {
+      if (await restoreClosedTab()) navigate("/");
+    },
+    "ctrl+shift+n": () => {
+      newWindow().catch((error) => console.error("Failed to open window:", error));
+    },
   };
   useEffect(() => {
     const handleKeyDown = (e: KeyboardEvent) => {
@@ -1,10 +1,11 @@
 import { getCurrentWindow } from "@tauri-apps/api/window";
+import { getAllWebviewWindows } from "@tauri-apps/api/webviewWindow";
 import { Minimize, Maximize, X } from "lucide-react";
 import { useEffect } from "react";
 import { useEditorActions } from "@/stores/editor";
 
 export default function WindowControls() {
-  const { persistSession } = useEditorActions();
+  const { persistSession, parkTabs } = useEditorActions();
 
   useEffect(() => {
     const appWindow = getCurrentWindow();
@@ -18,6 +19,15 @@ export default function WindowControls() {
     document.getElementById("titlebar-close")?.addEventListener("click", handleClose);
 
     const unlisten = appWindow.onCloseRequested(async () => {
+      // While other windows stay open this one isn't reopened next time; its
+      // tabs stay in its workspace instead. The last window to close keeps
+      // its tabs for the next start.
+      const others = (await getAllWebviewWindows()).filter((w) => w.label !== appWindow.label);
+      if (others.length > 0) {
+        await parkTabs();
+        return;
+      }
+
       await persistSession();
     });
 
@@ -1,9 +1,12 @@
 import { invoke } from "@tauri-apps/api/core";
-import { DatabaseV2, OpenedFile, SessionTab, TabMeta, TabType } from "@/lib/types";
+import { ClosedTab, EditorSession, FileVersion, LineEndingConversion, LoadedEditorState, LocalFile, MigrationPlan, MigrationReport, NoteMetadata, NoteRef, OpenedFile, OpenRequest, RankedFile, SavedFile, SaveOptions, SessionTab, Snapshot, TabMeta, TabType, ViewState, WindowState, WorkspaceSummary } from "@/lib/types";
 
-export async function saveFile(path: string, content: string) {
-  const message = await invoke("save_file", { entry: { path, content } });
-  return message;
+/**
+ * Write `content` to `path`. The backend keeps the encoding, BOM and line
+ * endings of the file on disk unless they are given explicitly.
+ */
+export async function saveFile(path: string, content: string, options?: SaveOptions): Promise<SavedFile> {
+  return await invoke<SavedFile>("save_file", { entry: { path, content, ...options } });
 }
 
 export async function openFile(path: string): Promise<OpenedFile | null> {
@@ -11,11 +14,74 @@ export async function openFile(path: string): Promise<OpenedFile | null> {
   return result;
 }
 
-export async function loadEditorState(): Promise<DatabaseV2> {
+/**
+ * Rewrite the line endings of a file on disk, keeping its encoding.
+ */
+export async function convertLineEndings(path: string, target: "lf" | "crlf"): Promise<LineEndingConversion> {
+  return await invoke<LineEndingConversion>("convert_line_endings", { path, target });
+}
+
+/**
+ * Replace the set of files the backend watches for external changes.
+ * The backend emits `file-changed`, `file-deleted` and `file-renamed` events
+ * for these paths.
+ */
+export async function watchOpenFiles(paths: string[]): Promise<void> {
+  return await invoke("watch_open_files", { paths });
+}
+
+/**
+ * Local version history helpers
+ */
+
+export async function listFileVersions(path: string): Promise<Snapshot[]> {
+  return await invoke<Snapshot[]>("list_file_versions", { path });
+}
+
+export async function readFileVersion(path: string, id: string): Promise<string> {
+  return await invoke<string>("read_file_version", { path, id });
+}
+
+export async function diffFileVersion(path: string, id: string): Promise<string> {
+  return await invoke<string>("diff_file_version", { path, id });
+}
+
+export async function restoreFileVersion(path: string, id: string): Promise<FileVersion> {
+  return await invoke<FileVersion>("restore_file_version", { path, id });
+}
+
+/**
+ * Crash-recovery journal helpers. Entries newer than the session file are
+ * replayed by `load_editor_state`.
+ */
+
+export async function journalTabContent(tab: TabMeta, content: string): Promise<void> {
+  return await invoke("journal_tab_content", {
+    tabId: tab.id,
+    filename: tab.filename,
+    path: tab.type === TabType.LOCAL ? tab.path : undefined,
+    content,
+    baseVersion: tab.baseVersion,
+  });
+}
+
+export async function journalTabClosed(tabId: string): Promise<void> {
+  return await invoke("journal_tab_closed", { tabId });
+}
+
+/**
+ * Load the calling window's tabs. The first window to load also reopens the
+ * other windows of the last session.
+ */
+export async function loadEditorState(): Promise<LoadedEditorState> {
   return await invoke("load_editor_state");
 }
 
-export async function saveEditorState(state: DatabaseV2): Promise<void> {
+/**
+ * Save the calling window's tabs. Saving no tabs while other windows are
+ * open drops this window from the session.
+ */
+export async function saveEditorState(state: WindowState): Promise<void> {
   return await invoke("save_editor_state", { state });
 }
 
@@ -26,13 +92,15 @@ export async function saveEditorState(state: DatabaseV2): Promise<void> {
  *  - dirty tabs (unsaved changes that can't be recovered from disk)
  *  - untitled tabs (no backing file at all)
  */
-export function serializeTabs(tabs: TabMeta[], contentMap: Record<string, string>): SessionTab[] {
+export function serializeTabs(tabs: TabMeta[], contentMap: Record<string, string>, viewStates: Record<string, ViewState> = {}): SessionTab[] {
   return tabs.map((tab) => ({
     id: tab.id,
     path: tab.type === TabType.LOCAL ? tab.path : undefined,
     filename: tab.filename,
     isDirty: tab.isDirty,
     content: tab.isDirty || tab.type === TabType.UNTITLED ? (contentMap[tab.id] ?? "") : undefined,
+    baseVersion: tab.isDirty ? tab.baseVersion : undefined,
+    view: viewStates[tab.id],
   }));
 }
 
@@ -42,13 +110,16 @@ export function serializeTabs(tabs: TabMeta[], contentMap: Record<string, string
  * Returns:
  *  - `tabs`         – metadata-only array (no content) for the store's `tabs` slice
  *  - `contentCache` – id → content for every tab that carried persisted content
+ *  - `viewStates`   – id → view state for every tab that carried one
  */
 export function deserializeTabs(sessionTabs: SessionTab[]): {
   tabs: TabMeta[];
   contentCache: Record<string, string>;
+  viewStates: Record<string, ViewState>;
 } {
   const tabs: TabMeta[] = [];
   const contentCache: Record<string, string> = {};
+  const viewStates: Record<string, ViewState> = {};
 
   for (const st of sessionTabs) {
     tabs.push({
@@ -57,14 +128,65 @@ export function deserializeTabs(sessionTabs: SessionTab[]): {
       filename: st.filename,
       path: st.path,
       isDirty: st.isDirty,
+      baseVersion: st.baseVersion,
     });
 
     if (st.content !== undefined) {
       contentCache[st.id] = st.content;
     }
+
+    if (st.view) {
+      viewStates[st.id] = st.view;
+    }
   }
 
-  return { tabs, contentCache };
+  return { tabs, contentCache, viewStates };
+}
+
+/**
+ * Remember where the user was in `path`, normally when its tab is closed, so
+ * reopening the file restores it.
+ */
+export async function rememberViewState(path: string, view: ViewState): Promise<void> {
+  return await invoke<void>("remember_view_state", { path, view });
+}
+
+/** The view state last remembered for `path`, if any. */
+export async function getViewState(path: string): Promise<ViewState | null> {
+  return await invoke<ViewState | null>("get_view_state", { path });
+}
+
+/** Count an open of `path` in a tab towards its frecency. */
+export async function recordFileOpened(path: string): Promise<LocalFile> {
+  return await invoke<LocalFile>("record_file_opened", { path });
+}
+
+/** The recent files, pinned ones first and the rest by frecency. */
+export async function listRecentFiles(limit?: number): Promise<RankedFile[]> {
+  return await invoke<RankedFile[]>("list_recent_files", { limit });
+}
+
+/** Pin `path` to the top of the recent files, or unpin it. */
+export async function setRecentFilePinned(path: string, pinned: boolean): Promise<void> {
+  return await invoke<void>("set_recent_file_pinned", { path, pinned });
+}
+
+/** Remember a tab the user just closed so it can be reopened. */
+export async function recordClosedTab(tab: SessionTab): Promise<void> {
+  return await invoke<void>("record_closed_tab", { tab });
+}
+
+/** The closed tabs that can be reopened, most recently closed first. */
+export async function listClosedTabs(): Promise<ClosedTab[]> {
+  return await invoke<ClosedTab[]>("list_closed_tabs");
+}
+
+/**
+ * Take closed tab `id` off the stack, or the most recently closed one when
+ * omitted. Resolves to `null` when there is nothing to reopen.
+ */
+export async function reopenClosedTab(id?: string): Promise<SessionTab | null> {
+  return await invoke<SessionTab | null>("reopen_closed_tab", { id });
 }
 
 /**
@@ -76,17 +198,139 @@ export async function checkForMigrationToV2(): Promise<boolean> {
   return needs;
 }
 
-export async function migrateV1ToV2(): Promise<boolean> {
-  const result = await invoke<boolean>("migrate_v1_to_v2");
+/**
+ * Preview the V1 -> V2 migration without touching disk. `destination`
+ * defaults to the notes home, or a folder in the documents dir on first use.
+ */
+export async function dryRunV1ToV2(destination?: string): Promise<MigrationPlan> {
+  return await invoke<MigrationPlan>("dry_run_v1_to_v2", { destination });
+}
+
+/**
+ * Run the V1 -> V2 migration. Listen to `migration-progress` for per-note
+ * updates while it runs. The destination is remembered as the notes home.
+ */
+export async function migrateV1ToV2(destination?: string): Promise<MigrationReport> {
+  const result = await invoke<MigrationReport>("migrate_v1_to_v2", { destination });
   return result;
 }
 
+/** V1 notes the last migration couldn't copy. They can be retried. */
+export async function listFailedV1Notes(): Promise<NoteRef[]> {
+  return await invoke<NoteRef[]>("list_failed_v1_notes");
+}
+
 /**
- * Drain and return file paths that were passed via CLI arguments on cold start
- * (e.g. the user right-clicked a file and chose "Open with Taking Notes").
+ * Try the notes the last migration couldn't copy again, into the same folder.
+ * Emits `migration-progress` like `migrateV1ToV2`.
+ */
+export async function retryFailedV1Notes(): Promise<MigrationReport> {
+  return await invoke<MigrationReport>("retry_failed_v1_notes");
+}
+
+/**
+ * Undo the last V1 -> V2 migration: notes go back to their V1 location and
+ * the migration will be offered again on next start.
+ */
+export async function rollbackV1ToV2(): Promise<string> {
+  return await invoke<string>("rollback_v1_to_v2");
+}
+
+/**
+ * Original V1 title, tag and dates of a migrated note, or null if `path`
+ * didn't come from V1.
+ */
+export async function getNoteMetadata(path: string): Promise<NoteMetadata | null> {
+  return await invoke<NoteMetadata | null>("get_note_metadata", { path });
+}
+
+export async function listNoteMetadata(): Promise<Record<string, NoteMetadata>> {
+  return await invoke<Record<string, NoteMetadata>>("list_note_metadata");
+}
+
+/**
+ * Drain and return the open requests queued for this window: the CLI
+ * arguments on cold start (e.g. the user right-clicked a file and chose
+ * "Open with Taking Notes"), or what a new window was opened for.
  *
  * This is a one-shot command — subsequent calls return an empty array.
  */
-export async function takeCliFilePaths(): Promise<string[]> {
-  return await invoke<string[]>("take_cli_file_paths");
+export async function takeCliFilePaths(): Promise<OpenRequest[]> {
+  return await invoke<OpenRequest[]>("take_cli_file_paths");
+}
+
+/**
+ * Release the `--wait` caller that opened a tab.
+ */
+export async function finishWait(waitId: string): Promise<void> {
+  await invoke("finish_wait", { waitId });
+}
+
+/**
+ * Unified diff between two files on disk.
+ */
+export async function diffFiles(left: string, right: string): Promise<string> {
+  return await invoke<string>("diff_files", { left, right });
+}
+
+/**
+ * Window helpers
+ */
+
+/** Open an empty window. Resolves to its label. */
+export async function newWindow(): Promise<string> {
+  return await invoke<string>("new_window");
+}
+
+/**
+ * Hand a tab over to the window labelled `target`, or to a new window when
+ * omitted. Resolves to the label of the window that received it; the caller
+ * should then drop its own copy.
+ */
+export async function moveTabToWindow(tab: SessionTab, options: { readOnly: boolean; waitId?: string }, target?: string): Promise<string> {
+  return await invoke<string>("move_tab_to_window", { tab, ...options, target });
+}
+
+/**
+ * Workspace helpers
+ */
+
+export async function listWorkspaces(): Promise<WorkspaceSummary[]> {
+  return await invoke<WorkspaceSummary[]>("list_workspaces");
+}
+
+/** Add an empty workspace without showing it. */
+export async function createWorkspace(name: string): Promise<WorkspaceSummary> {
+  return await invoke<WorkspaceSummary>("create_workspace", { name });
+}
+
+export async function renameWorkspace(id: string, name: string): Promise<void> {
+  return await invoke<void>("rename_workspace", { id, name });
+}
+
+/** Copy a workspace and its tabs. `name` defaults to "<name> copy". */
+export async function duplicateWorkspace(id: string, name?: string): Promise<WorkspaceSummary> {
+  return await invoke<WorkspaceSummary>("duplicate_workspace", { id, name });
+}
+
+/** Fails for a workspace shown in a window and for the last workspace. */
+export async function deleteWorkspace(id: string): Promise<void> {
+  return await invoke<void>("delete_workspace", { id });
+}
+
+/**
+ * Show workspace `id` in this window. `state`, the window's current tabs, is
+ * saved and parked in the workspace being left. Resolves to the tabs to show,
+ * or `null` when another window shows the workspace and was focused instead.
+ */
+export async function switchWorkspace(id: string, state: WindowState): Promise<EditorSession | null> {
+  return await invoke<EditorSession | null>("switch_workspace", { id, state });
+}
+
+/**
+ * Drop this window from the session because it is closing while others stay
+ * open. `state`, its current tabs, is saved and kept in its workspace.
+ */
+export async function detachWindow(state: WindowState): Promise<void> {
+  return await invoke<void>("detach_window", { state });
 }
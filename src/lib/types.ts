@@ -1,6 +1,8 @@
 export enum SchemaVersion {
   V1 = "V1",
   V2 = "V2",
+  V3 = "V3",
+  V4 = "V4",
 }
 
 export interface SessionTab {
@@ -9,6 +11,41 @@ export interface SessionTab {
   filename: string;
   isDirty: boolean;
   content?: string; // Only for dirty/untitled tabs
+  /** Version of the file the unsaved edits in `content` were made on. */
+  baseVersion?: FileVersion;
+  view?: ViewState;
+}
+
+/**
+ * A selection as offsets into the text. `anchor` is where it started and
+ * `head` where the cursor is, so `anchor === head` is a plain cursor.
+ */
+export interface SelectionRange {
+  anchor: number;
+  head: number;
+}
+
+/** A folded block of lines, 1-based and inclusive. */
+export interface FoldRange {
+  fromLine: number;
+  toLine: number;
+}
+
+/**
+ * Where the user was in a document: selections (the first is the primary
+ * one), scroll offsets in pixels and folded blocks.
+ */
+export interface ViewState {
+  selections: SelectionRange[];
+  scrollTop: number;
+  scrollLeft: number;
+  folds?: FoldRange[];
+}
+
+/** A tab closed by the user, kept so it can be reopened. */
+export interface ClosedTab extends SessionTab {
+  /** When the tab was closed, unix milliseconds. */
+  closedAt: number;
 }
 
 export interface EditorSession {
@@ -21,12 +58,64 @@ export interface LocalFile {
   filename: string;
   modified: number; // Unix timestamp in ms
   path: string;
+  /** How many times the file was opened in a tab. Counted by the backend. */
+  openCount?: number;
+  /** When the file was last opened in a tab, unix ms. */
+  lastOpened?: number;
+  /** Pinned files are listed first and never pruned. */
+  pinned?: boolean;
+  /** The file wasn't found when the session was loaded. Only pinned files are kept while missing. */
+  missing?: boolean;
+}
+
+/** A recent file with its frecency score, as returned by `list_recent_files`. */
+export interface RankedFile extends LocalFile {
+  score: number;
 }
 
-export interface DatabaseV2 {
+/**
+ * What a window loads and saves: its own tabs plus the recent files shared
+ * by every window. The backend keeps the sessions of all windows together.
+ */
+export interface WindowState {
   recentFiles: Record<string, LocalFile>; // path -> LocalFile
   session: EditorSession;
-  schemaVersion: "V2";
+}
+
+/**
+ * Something the user should know about after loading the session.
+ */
+export type LoadWarning =
+  | {
+      kind: "sessionQuarantined";
+      /** Where the unreadable session file was moved, if that succeeded. */
+      backupPath: string | null;
+      error: string;
+      recoveredTabs: number;
+      recoveredRecentFiles: number;
+    }
+  | { kind: "recoveredFromJournal"; tabs: number }
+  /** Files changed on disk since their restored unsaved edits were made. */
+  | { kind: "changedOnDisk"; paths: string[] }
+  /** Files whose unsaved edits couldn't be read back. */
+  | { kind: "unsavedEditsLost"; paths: string[] };
+
+/**
+ * A named set of tabs. Each window shows one workspace at a time.
+ */
+export interface WorkspaceSummary {
+  id: string;
+  name: string;
+  tabCount: number;
+  /** Label of the window showing the workspace, if any. */
+  window?: string;
+}
+
+/**
+ * Returned by `load_editor_state`: the window's state plus any load warnings.
+ */
+export interface LoadedEditorState extends WindowState {
+  warnings: LoadWarning[];
 }
 
 /**
@@ -42,6 +131,11 @@ export interface TabMeta {
   filename: string;
   path?: string;
   isDirty: boolean;
+  readOnly?: boolean;
+  /** Set while a `--wait` caller is blocked until this tab is saved or closed. */
+  waitId?: string;
+  /** Version of the file when the unsaved edits started. Only for dirty local tabs. */
+  baseVersion?: FileVersion;
 }
 
 /**
@@ -60,14 +154,43 @@ export interface Tab extends TabMeta {
 export interface FileInfo {
   /** Detected line-ending style: "CRLF", "LF", "Mixed", or "N/A". */
   lineEnding: string;
-  /** Encoding used to read the file (always "UTF-8" for now). */
+  /** Detected encoding used to decode the file, e.g. "UTF-8", "UTF-16LE", "windows-1252". */
   encoding: string;
+  /** Whether the file starts with a byte-order mark. */
+  hasBom: boolean;
   /** On-disk file size in bytes at the moment it was read. */
   fileSize: number;
   /** File extension without the leading dot, e.g. "txt", "md". Empty string when none. */
   extension: string;
+  /** Version token to send back to `save_file` for conflict detection. Absent for untitled tabs. */
+  version?: FileVersion;
+}
+
+/**
+ * Snapshot of a file's on-disk state (mtime + size + content hash).
+ */
+export interface FileVersion {
+  modified: number;
+  size: number;
+  hash: string;
+}
+
+/**
+ * Returned by `save_file` on success.
+ */
+export interface SavedFile {
+  message: string;
+  version: FileVersion;
 }
 
+/**
+ * Structured error thrown by `save_file`. A `conflict` means the file was
+ * changed by another program; retry with `force: true` to overwrite anyway.
+ */
+export type SaveError =
+  | { kind: "conflict"; expected: FileVersion; actual: FileVersion | null }
+  | { kind: "io"; message: string };
+
 /**
  * Bundle returned by the `open_file` Tauri command so the frontend gets
  * content + metadata in a single IPC round-trip.
@@ -77,6 +200,142 @@ export interface OpenedFile {
   fileInfo: FileInfo;
 }
 
+/**
+ * Optional overrides for `save_file`. Anything omitted is taken from the file
+ * currently on disk.
+ */
+export interface SaveOptions {
+  encoding?: string;
+  withBom?: boolean;
+  /** "preserve" (default) keeps the on-disk style. */
+  lineEnding?: "preserve" | "lf" | "crlf";
+  /** Refuse to save if the file on disk no longer matches this version. */
+  expectedVersion?: FileVersion;
+  /** Overwrite even when `expectedVersion` doesn't match. */
+  force?: boolean;
+}
+
+/**
+ * Result of the `convert_line_endings` command.
+ */
+export interface LineEndingConversion {
+  lineEnding: string;
+  changedLines: number;
+}
+
+/** Payload of the `file-changed` and `file-deleted` events. */
+export interface FileChangedEvent {
+  path: string;
+}
+
+/** Payload of the `file-renamed` event. */
+export interface FileRenamedEvent {
+  from: string;
+  to: string;
+}
+
+/**
+ * One stored version of a file in the local history. Returned by
+ * `list_file_versions`, newest first.
+ */
+export interface Snapshot {
+  id: string;
+  /** Unix timestamp in ms. */
+  createdAt: number;
+  size: number;
+  storedSize: number;
+  hash: string;
+}
+
+/** What a command-line argument asked the editor to open. */
+export type OpenTarget =
+  | { kind: "file"; path: string; newFile: boolean; line?: number; column?: number }
+  | { kind: "directory"; path: string }
+  | { kind: "diff"; left: string; right: string }
+  /** Piped input (`-`), already read by the backend. */
+  | { kind: "stdin"; filename: string; content: string }
+  /** A tab moved over from another window, with its unsaved content. */
+  | { kind: "tab"; tab: SessionTab };
+
+/**
+ * One open request, from `take_cli_file_paths` when a window starts or the
+ * `open-files` event when a second instance is launched or a tab is moved
+ * to this window.
+ */
+export type OpenRequest = OpenTarget & {
+  readOnly: boolean;
+  newWindow: boolean;
+  /** Pass to `finish_wait` once the tab is saved or closed (`--wait`). */
+  waitId?: string;
+};
+
+/** A V1 note, as referenced by migration plans and reports. */
+export interface NoteRef {
+  tag: string;
+  title: string;
+  /** Path of the note's V1 file. */
+  source: string;
+}
+
+/** Result of the `dry_run_v1_to_v2` command. Nothing has been written. */
+export interface MigrationPlan {
+  destination: string;
+  mapping: (NoteRef & { destination: string; action: "copy" | "skip" })[];
+  /** Notes whose title-based file name was taken and got a numbered suffix. */
+  conflicts: { tag: string; wanted: string; resolved: string }[];
+  missing: NoteRef[];
+}
+
+export interface MigratedFile {
+  tag: string;
+  source: string;
+  destination: string;
+  checksum: string;
+  reused: boolean;
+}
+
+/** Result of the `migrate_v1_to_v2` command. */
+export interface MigrationReport {
+  destination: string;
+  migrated: MigratedFile[];
+  skipped: (NoteRef & { destination: string; reason: string })[];
+  missing: NoteRef[];
+  failed: (NoteRef & { reason: string })[];
+}
+
+/** Payload of the `migration-progress` event, emitted after each note. */
+export interface MigrationProgress {
+  current: number;
+  total: number;
+  tag: string;
+  title: string;
+  outcome: "migrated" | "skipped" | "missing" | "failed";
+}
+
+/** Payload of the `file-changed` and `file-deleted` events for an open file. */
+export interface FileChangedEvent {
+  path: string;
+}
+
+/** Payload of the `file-renamed` event for an open file. */
+export interface FileRenamedEvent {
+  from: string;
+  to: string;
+}
+
+/**
+ * What V1 knew about a migrated note. Returned by `get_note_metadata` and
+ * `list_note_metadata`.
+ */
+export interface NoteMetadata {
+  title: string;
+  tag: string;
+  /** Unix timestamp in ms. */
+  createdAt: number;
+  /** Unix timestamp in ms. */
+  updatedAt: number;
+}
+
 export enum TabType {
   LOCAL = "local",
   UNTITLED = "untitled",
@@ -3,6 +3,8 @@ import { BrowserRouter, Routes, Route } from "react-router";
 import RootLayout from "./App";
 import HomePage from "@/pages/editor-page";
 import RecentFilesPage from "@/pages/recent-files-page";
+import WorkspacesPage from "@/pages/workspaces-page";
+import ClosedTabsPage from "@/pages/closed-tabs-page";
 
 const root = document.getElementById("root")!;
 
@@ -12,6 +14,8 @@ ReactDOM.createRoot(root).render(
       <Route element={<RootLayout />}>
         <Route index element={<HomePage />} />
         <Route path="/recent-files" element={<RecentFilesPage />} />
+        <Route path="/workspaces" element={<WorkspacesPage />} />
+        <Route path="/closed-tabs" element={<ClosedTabsPage />} />
       </Route>
     </Routes>
   </BrowserRouter>,
@@ -0,0 +1,69 @@
+import { useEffect, useState } from "react";
+import { useNavigate } from "react-router";
+import { FileText, History } from "lucide-react";
+import { useEditorActions } from "@/stores/editor";
+import { listClosedTabs } from "@/lib/commands";
+import { ClosedTab } from "@/lib/types";
+
+export default function ClosedTabsPage() {
+  const { restoreClosedTab } = useEditorActions();
+  const [closedTabs, setClosedTabs] = useState<ClosedTab[]>([]);
+  const navigate = useNavigate();
+
+  useEffect(() => {
+    listClosedTabs()
+      .then(setClosedTabs)
+      .catch((error) => console.error("Failed to list closed tabs:", error));
+  }, []);
+
+  const formatDate = (timestamp: number) => {
+    const date = new Date(timestamp);
+    return (
+      date.toLocaleDateString("es-AR", { day: "2-digit", month: "long", year: "numeric" }) +
+      " " +
+      date.toLocaleTimeString([], { hour: "2-digit", minute: "2-digit" })
+    );
+  };
+
+  return (
+    <div className="h-full relative flex flex-col text-sm bg-neutral-950">
+      <div className="w-full border-b border-neutral-800 p-4">
+        <h1 className="text-lg font-semibold text-white flex items-center gap-2">
+          <History className="w-5 h-5 text-blue-400" />
+          Recently Closed
+        </h1>
+      </div>
+
+      <div className="overflow-y-auto custom-scrollbar flex-1 p-2">
+        {closedTabs.length === 0 ? (
+          <div className="flex flex-col items-center justify-center h-64 text-neutral-500">
+            <FileText className="w-12 h-12 mb-4 opacity-20" />
+            <p>No closed tabs</p>
+          </div>
+        ) : (
+          closedTabs.map((tab) => (
+            <div
+              onClick={async () => {
+                await restoreClosedTab(tab.id);
+                navigate("/");
+              }}
+              className="flex cursor-pointer items-center gap-3 py-3 px-3 hover:bg-white/5 rounded-md mb-1 group transition-colors"
+              key={tab.id}
+            >
+              <div className="p-2 rounded bg-neutral-900 text-neutral-400 group-hover:text-blue-400 transition-colors">
+                <FileText className="w-4 h-4" />
+              </div>
+              <div className="min-w-0">
+                <p className="text-neutral-100 font-medium truncate">{tab.filename}</p>
+                <p className="text-neutral-500 text-xs truncate" title={tab.path}>
+                  {tab.path ?? "Untitled"}
+                </p>
+                <p className="text-neutral-600 text-[10px] mt-0.5">{formatDate(tab.closedAt)}</p>
+              </div>
+            </div>
+          ))
+        )}
+      </div>
+    </div>
+  );
+}
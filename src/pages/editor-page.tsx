@@ -1,12 +1,71 @@
-import { useCurrentTabMeta, useActiveContent, useEditorActions } from "@/stores/editor";
-import { useRef } from "react";
+import { useCurrentTabMeta, useActiveContent, useCursorRequest, useEditorActions, getTabViewState } from "@/stores/editor";
+import { useEffect, useLayoutEffect, useRef } from "react";
+import { ViewState } from "@/lib/types";
+
+/** Offset of a 1-based line and column in `text`, clamped to its bounds. */
+function offsetOf(text: string, line: number, column: number): number {
+  let offset = 0;
+  for (let i = 1; i < line; i++) {
+    const next = text.indexOf("\n", offset);
+    if (next === -1) return text.length;
+    offset = next + 1;
+  }
+  const lineEnd = text.indexOf("\n", offset);
+  const end = lineEnd === -1 ? text.length : lineEnd;
+  return Math.min(offset + column - 1, end);
+}
+
+/** Where the user is in `textarea`. A textarea has one selection and no folds. */
+function viewOf(textarea: HTMLTextAreaElement): ViewState {
+  const { selectionStart, selectionEnd, selectionDirection } = textarea;
+  const selection =
+    selectionDirection === "backward" ? { anchor: selectionEnd, head: selectionStart } : { anchor: selectionStart, head: selectionEnd };
+
+  return {
+    selections: [selection],
+    scrollTop: Math.round(textarea.scrollTop),
+    scrollLeft: Math.round(textarea.scrollLeft),
+  };
+}
 
 export default function HomePage() {
   const currentTab = useCurrentTabMeta();
   const activeContent = useActiveContent();
-  const { setContent } = useEditorActions();
+  const cursorRequest = useCursorRequest();
+  const { setContent, clearCursorRequest, setViewState } = useEditorActions();
   const textareaRef = useRef<HTMLTextAreaElement>(null);
 
+  // Put the cursor and scroll position back where they were when the tab
+  // was last shown, before the browser paints the tab from the top.
+  useLayoutEffect(() => {
+    const textarea = textareaRef.current;
+    if (!textarea || !currentTab) return;
+
+    const view = getTabViewState(currentTab.id);
+    const length = textarea.value.length;
+    const anchor = Math.min(view?.selections[0]?.anchor ?? 0, length);
+    const head = Math.min(view?.selections[0]?.head ?? 0, length);
+
+    textarea.setSelectionRange(Math.min(anchor, head), Math.max(anchor, head), head < anchor ? "backward" : "forward");
+    textarea.scrollTop = view?.scrollTop ?? 0;
+    textarea.scrollLeft = view?.scrollLeft ?? 0;
+  }, [currentTab?.id]);
+
+  const reportView = () => {
+    const textarea = textareaRef.current;
+    if (textarea && currentTab) setViewState(currentTab.id, viewOf(textarea));
+  };
+
+  useEffect(() => {
+    const textarea = textareaRef.current;
+    if (!cursorRequest || !textarea || cursorRequest.tabId !== currentTab?.id) return;
+
+    const offset = offsetOf(activeContent, cursorRequest.line, cursorRequest.column);
+    textarea.focus();
+    textarea.setSelectionRange(offset, offset);
+    clearCursorRequest();
+  }, [cursorRequest, currentTab?.id, activeContent]);
+
   if (!currentTab) {
     return (
       <div className="flex h-full items-center justify-center text-neutral-500">
@@ -27,9 +86,15 @@ export default function HomePage() {
           scrollbarGutter: "stable",
         }}
         value={activeContent}
+        readOnly={currentTab.readOnly}
         onChange={(e) => {
           setContent(e.target.value);
+          reportView();
         }}
+        onSelect={reportView}
+        onKeyUp={reportView}
+        onMouseUp={reportView}
+        onScroll={reportView}
         autoFocus
         spellCheck={false}
       />
@@ -1,27 +1,33 @@
 import RecentFilesPageLoader from "@/components/recent-files-page-loader";
-import { useRecentFiles, useEditorActions } from "@/stores/editor";
+import { useEditorActions } from "@/stores/editor";
 import { useEffect, useMemo, useState } from "react";
-import { FolderSearch, FileText, Clock } from "lucide-react";
+import { FolderSearch, FileText, Clock, Pin, PinOff } from "lucide-react";
 import { revealItemInDir } from "@tauri-apps/plugin-opener";
 import { useNavigate } from "react-router";
+import { listRecentFiles, setRecentFilePinned } from "@/lib/commands";
+import { RankedFile } from "@/lib/types";
 
 export default function RecentFilesPage() {
-  const recentFiles = useRecentFiles();
-  const { openByPath, resetCurrent } = useEditorActions();
+  const [recentFiles, setRecentFiles] = useState<RankedFile[] | null>(null);
+  const { openByPath, resetCurrent, persistSession } = useEditorActions();
   const [searchQuery, setSearchQuery] = useState("");
   const navigate = useNavigate();
 
-  const filteredNotes = useMemo(() => {
-    const files = Object.values(recentFiles);
+  // Save first so files opened since the last save are ranked too.
+  const refresh = () =>
+    persistSession()
+      .then(() => listRecentFiles())
+      .then(setRecentFiles)
+      .catch((error) => console.error("Failed to list recent files:", error));
 
-    const sorted = files.sort((a, b) => {
-      return b.modified - a.modified;
-    });
+  const filteredNotes = useMemo(() => {
+    // Already ranked: pinned files first, then by frecency.
+    const files = recentFiles ?? [];
 
-    if (!searchQuery.trim()) return sorted;
+    if (!searchQuery.trim()) return files;
 
     const query = searchQuery.toLowerCase();
-    return sorted.filter((note) => note.filename?.toLowerCase().includes(query));
+    return files.filter((note) => note.filename?.toLowerCase().includes(query));
   }, [recentFiles, searchQuery]);
 
   const formatDate = (timestamp: number) => {
@@ -33,14 +39,15 @@ export default function RecentFilesPage() {
     );
   };
 
-  if (recentFiles === null) {
-    return <RecentFilesPageLoader />;
-  }
-
   useEffect(() => {
+    refresh();
     resetCurrent();
   }, []);
 
+  if (recentFiles === null) {
+    return <RecentFilesPageLoader />;
+  }
+
   return (
     <div className="h-full relative flex flex-col text-sm bg-neutral-950">
       <div className="w-full border-b border-neutral-800 p-4">
@@ -81,23 +88,43 @@ export default function RecentFilesPage() {
                   <p className="text-neutral-500 text-xs truncate" title={note.path}>
                     {note.path}
                   </p>
-                  <p className="text-neutral-600 text-[10px] mt-0.5">{formatDate(note.modified)}</p>
+                  <p className="text-neutral-600 text-[10px] mt-0.5">{note.missing ? "File not found" : formatDate(note.modified)}</p>
                 </div>
               </div>
 
-              <button
-                className="h-fit p-2 cursor-pointer rounded-md border border-neutral-800 text-neutral-600 hover:text-neutral-50 hover:border-neutral-600 hover:bg-neutral-800 opacity-0 group-hover:opacity-100"
-                style={{
-                  transition: "color 150ms ease, border-color 150ms ease, background-color 150ms ease, transform 150ms ease, opacity 150ms ease",
-                }}
-                onClick={async (e) => {
-                  e.stopPropagation();
-                  await revealItemInDir(note.path);
-                }}
-                title="Reveal in file explorer"
-              >
-                <FolderSearch className="w-4 h-4" />
-              </button>
+              <div className="flex gap-x-2">
+                <button
+                  className={`h-fit p-2 cursor-pointer rounded-md border border-neutral-800 hover:text-neutral-50 hover:border-neutral-600 hover:bg-neutral-800 ${note.pinned ? "text-blue-400" : "text-neutral-600 opacity-0 group-hover:opacity-100"}`}
+                  style={{
+                    transition: "color 150ms ease, border-color 150ms ease, background-color 150ms ease, transform 150ms ease, opacity 150ms ease",
+                  }}
+                  onClick={async (e) => {
+                    e.stopPropagation();
+                    try {
+                      await setRecentFilePinned(note.path, !note.pinned);
+                    } catch (error) {
+                      console.error("Failed to pin file:", error);
+                    }
+                    await refresh();
+                  }}
+                  title={note.pinned ? "Unpin" : "Pin"}
+                >
+                  {note.pinned ? <PinOff className="w-4 h-4" /> : <Pin className="w-4 h-4" />}
+                </button>
+                <button
+                  className="h-fit p-2 cursor-pointer rounded-md border border-neutral-800 text-neutral-600 hover:text-neutral-50 hover:border-neutral-600 hover:bg-neutral-800 opacity-0 group-hover:opacity-100"
+                  style={{
+                    transition: "color 150ms ease, border-color 150ms ease, background-color 150ms ease, transform 150ms ease, opacity 150ms ease",
+                  }}
+                  onClick={async (e) => {
+                    e.stopPropagation();
+                    await revealItemInDir(note.path);
+                  }}
+                  title="Reveal in file explorer"
+                >
+                  <FolderSearch className="w-4 h-4" />
+                </button>
+              </div>
             </div>
           ))
         )}
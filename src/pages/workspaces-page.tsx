@@ -0,0 +1,165 @@
+import { useEffect, useState } from "react";
+import { useNavigate } from "react-router";
+import { Copy, Layers, Pencil, Trash2 } from "lucide-react";
+import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
+import { message } from "@tauri-apps/plugin-dialog";
+import { useEditorActions } from "@/stores/editor";
+import { createWorkspace, deleteWorkspace, duplicateWorkspace, listWorkspaces, renameWorkspace } from "@/lib/commands";
+import { WorkspaceSummary } from "@/lib/types";
+
+export default function WorkspacesPage() {
+  const { openWorkspace, persistSession } = useEditorActions();
+  const [workspaces, setWorkspaces] = useState<WorkspaceSummary[]>([]);
+  const [newName, setNewName] = useState("");
+  const [renaming, setRenaming] = useState<{ id: string; name: string } | null>(null);
+  const navigate = useNavigate();
+  const currentLabel = getCurrentWebviewWindow().label;
+
+  const refresh = () =>
+    listWorkspaces()
+      .then(setWorkspaces)
+      .catch((error) => console.error("Failed to list workspaces:", error));
+
+  // Runs `action` and refreshes the list, reporting failures to the user.
+  const run = async (action: () => Promise<unknown>) => {
+    try {
+      await action();
+    } catch (error) {
+      await message(String(error), { title: "Workspaces", kind: "error" });
+    }
+    await refresh();
+  };
+
+  useEffect(() => {
+    refresh();
+    const unlisten = getCurrentWebviewWindow().onFocusChanged(({ payload: focused }) => {
+      if (focused) refresh();
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  const handleCreate = () =>
+    run(async () => {
+      const created = await createWorkspace(newName);
+      setNewName("");
+      if (await openWorkspace(created.id)) navigate("/");
+    });
+
+  const handleRename = () => {
+    if (!renaming) return;
+    const { id, name } = renaming;
+    setRenaming(null);
+    run(() => renameWorkspace(id, name));
+  };
+
+  const handleDuplicate = (workspace: WorkspaceSummary) =>
+    run(async () => {
+      // Copy what this window shows now, not what it last saved.
+      if (workspace.window === currentLabel) await persistSession();
+      await duplicateWorkspace(workspace.id);
+    });
+
+  const statusOf = (workspace: WorkspaceSummary) => {
+    if (workspace.window === currentLabel) return "This window";
+    if (workspace.window) return "Open in another window";
+    return null;
+  };
+
+  return (
+    <div className="h-full relative flex flex-col text-sm bg-neutral-950">
+      <div className="w-full border-b border-neutral-800 p-4">
+        <h1 className="text-lg font-semibold text-white mb-4 flex items-center gap-2">
+          <Layers className="w-5 h-5 text-blue-400" />
+          Workspaces
+        </h1>
+        <input
+          className="w-full px-3 py-2 rounded-md border border-neutral-800 outline-none text-neutral-50 bg-neutral-900 placeholder:text-neutral-600 focus:border-blue-500/50 transition-colors"
+          placeholder="New workspace name, then Enter..."
+          value={newName}
+          onChange={(e) => setNewName(e.target.value)}
+          onKeyDown={(e) => {
+            if (e.key === "Enter") handleCreate();
+          }}
+        />
+      </div>
+
+      <div className="overflow-y-auto custom-scrollbar flex-1 p-2">
+        {workspaces.map((workspace) => (
+          <div
+            onClick={async () => {
+              if (renaming?.id === workspace.id) return;
+              if (await openWorkspace(workspace.id)) navigate("/");
+            }}
+            className="flex cursor-pointer justify-between items-center py-3 px-3 hover:bg-white/5 rounded-md mb-1 group transition-colors"
+            key={workspace.id}
+          >
+            <div className="flex items-center gap-3 min-w-0 flex-1">
+              <div className="p-2 rounded bg-neutral-900 text-neutral-400 group-hover:text-blue-400 transition-colors">
+                <Layers className="w-4 h-4" />
+              </div>
+              <div className="min-w-0 flex-1">
+                {renaming?.id === workspace.id ? (
+                  <input
+                    autoFocus
+                    className="w-full px-2 py-0.5 rounded border border-neutral-800 outline-none text-neutral-50 bg-neutral-900 focus:border-blue-500/50"
+                    value={renaming.name}
+                    onClick={(e) => e.stopPropagation()}
+                    onChange={(e) => setRenaming({ id: workspace.id, name: e.target.value })}
+                    onBlur={() => setRenaming(null)}
+                    onKeyDown={(e) => {
+                      if (e.key === "Enter") handleRename();
+                      if (e.key === "Escape") setRenaming(null);
+                    }}
+                  />
+                ) : (
+                  <p className="text-neutral-100 font-medium truncate">{workspace.name}</p>
+                )}
+                <p className="text-neutral-500 text-xs">
+                  {workspace.tabCount} tab{workspace.tabCount === 1 ? "" : "s"}
+                  {statusOf(workspace) && <span className="text-blue-400/80"> · {statusOf(workspace)}</span>}
+                </p>
+              </div>
+            </div>
+
+            <div className="flex gap-x-2 opacity-0 group-hover:opacity-100" style={{ transition: "opacity 150ms ease" }}>
+              <button
+                className="h-fit p-2 cursor-pointer rounded-md border border-neutral-800 text-neutral-600 hover:text-neutral-50 hover:border-neutral-600 hover:bg-neutral-800"
+                onClick={(e) => {
+                  e.stopPropagation();
+                  setRenaming({ id: workspace.id, name: workspace.name });
+                }}
+                title="Rename"
+              >
+                <Pencil className="w-4 h-4" />
+              </button>
+              <button
+                className="h-fit p-2 cursor-pointer rounded-md border border-neutral-800 text-neutral-600 hover:text-neutral-50 hover:border-neutral-600 hover:bg-neutral-800"
+                onClick={(e) => {
+                  e.stopPropagation();
+                  handleDuplicate(workspace);
+                }}
+                title="Duplicate"
+              >
+                <Copy className="w-4 h-4" />
+              </button>
+              <button
+                className="h-fit p-2 cursor-pointer rounded-md border border-neutral-800 text-neutral-600 hover:text-red-500 hover:border-red-500/50 hover:bg-red-500/10 disabled:opacity-30 disabled:pointer-events-none"
+                disabled={workspace.window !== undefined}
+                onClick={(e) => {
+                  e.stopPropagation();
+                  run(() => deleteWorkspace(workspace.id));
+                }}
+                title={workspace.window ? "Switch its window to another workspace to delete it" : "Delete"}
+              >
+                <Trash2 className="w-4 h-4" />
+              </button>
+            </div>
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+}
@@ -1,9 +1,48 @@
-import { save as tauriSave, open as tauriOpen, ask } from "@tauri-apps/plugin-dialog";
+import { save as tauriSave, open as tauriOpen, ask, message } from "@tauri-apps/plugin-dialog";
 import { create } from "zustand";
-import { DatabaseV2, FileInfo, LocalFile, SessionTab, TabMeta, TabType } from "@/lib/types";
-import { deserializeTabs, loadEditorState, openFile, saveEditorState, saveFile, serializeTabs, takeCliFilePaths } from "@/lib/commands";
+import { EditorSession, FileInfo, FileVersion, LoadWarning, LocalFile, OpenRequest, SaveError, SessionTab, TabMeta, TabType, ViewState, WindowState } from "@/lib/types";
+import { deserializeTabs, detachWindow, diffFiles, finishWait, getViewState, loadEditorState, moveTabToWindow, openFile, recordClosedTab, recordFileOpened, rememberViewState, reopenClosedTab, saveEditorState, saveFile, serializeTabs, switchWorkspace, takeCliFilePaths, watchOpenFiles, journalTabContent, journalTabClosed } from "@/lib/commands";
 import { useShallow } from "zustand/shallow";
 
+/** Text of an error thrown by `save_file`, for showing to the user. */
+function describeSaveError(error: unknown): string {
+  const saveError = error as SaveError;
+  return saveError?.kind === "io" ? saveError.message : String(error);
+}
+
+/**
+ * Save a tab's content as it is being closed. When the file changed on disk
+ * since the edits began, the user chooses whether to overwrite it. Returns
+ * false when nothing was saved, so the tab stays open with its edits.
+ */
+async function saveBeforeClose(path: string, content: string, baseVersion: FileVersion | undefined): Promise<boolean> {
+  try {
+    await saveFile(path, content, { expectedVersion: baseVersion });
+    return true;
+  } catch (error) {
+    if ((error as SaveError)?.kind !== "conflict") {
+      await message(`Couldn't save "${path}": ${describeSaveError(error)}`, { title: "Save Failed", kind: "error" });
+      return false;
+    }
+  }
+
+  const overwrite = await ask(`"${path}" was changed by another program since you started editing it. Overwrite it with your changes?`, {
+    title: "File Changed on Disk",
+    kind: "warning",
+    okLabel: "Overwrite",
+    cancelLabel: "Keep Editing",
+  });
+  if (!overwrite) return false;
+
+  try {
+    await saveFile(path, content, { force: true });
+    return true;
+  } catch (error) {
+    await message(`Couldn't save "${path}": ${describeSaveError(error)}`, { title: "Save Failed", kind: "error" });
+    return false;
+  }
+}
+
 // ---------------------------------------------------------------------------
 // State shape
 // ---------------------------------------------------------------------------
@@ -12,6 +51,7 @@ import { useShallow } from "zustand/shallow";
 const DEFAULT_FILE_INFO: FileInfo = {
   lineEnding: "LF",
   encoding: "UTF-8",
+  hasBom: false,
   fileSize: 0,
   extension: "",
 };
@@ -40,6 +80,13 @@ type State = {
    */
   contentCache: Record<string, string>;
 
+  /**
+   * Cursor, selection and scroll position of each open tab, reported by the
+   * editor as they change. Kept apart from `tabs` so scrolling doesn't
+   * re-render the tab bar.
+   */
+  viewStates: Record<string, ViewState>;
+
   /**
    * File metadata (encoding, line ending, size, extension) for the
    * currently active tab. Populated when a file is opened from disk,
@@ -50,6 +97,12 @@ type State = {
 
   recentFiles: Record<string, LocalFile>;
   isInitialized: boolean;
+
+  /**
+   * Position (1-based) the editor should move the cursor to once `tabId` is
+   * shown, e.g. from `file.txt:42:7` on the command line.
+   */
+  cursorRequest: { tabId: string; line: number; column: number } | null;
 };
 
 // ---------------------------------------------------------------------------
@@ -60,13 +113,24 @@ type Actions = {
   initialize: () => Promise<void>;
   persistSession: () => Promise<void>;
   addBlank: () => void;
+  addUntitled: (filename: string, content: string, options?: { readOnly?: boolean }) => void;
+  openRequests: (requests: OpenRequest[]) => Promise<void>;
+  clearCursorRequest: () => void;
+  setViewState: (tabId: string, view: ViewState) => void;
   setContent: (content: string) => void;
   saveCurrentFileOnDisk: () => Promise<void>;
   openLocalFile: () => Promise<void>;
   openByPath: (path: string) => Promise<void>;
   openTab: (id: string) => Promise<void>;
-  closeTab: (id: string, options?: { skipConfirmation?: boolean }) => Promise<boolean>;
+  closeTab: (id: string, options?: { skipConfirmation?: boolean; moved?: boolean }) => Promise<boolean>;
   closeCurrentTab: () => Promise<boolean>;
+  restoreClosedTab: (id?: string) => Promise<boolean>;
+  moveTab: (id: string, target?: string) => Promise<void>;
+  openWorkspace: (id: string) => Promise<boolean>;
+  parkTabs: () => Promise<void>;
+  fileChangedOnDisk: (path: string) => Promise<void>;
+  fileDeletedOnDisk: (path: string) => Promise<void>;
+  fileRenamedOnDisk: (from: string, to: string) => void;
   resetCurrent: () => void;
 };
 
@@ -108,6 +172,29 @@ function buildContentMap(state: Pick<State, "currentTabId" | "activeContent" | "
   return map;
 }
 
+/** Everything this window saves to the session: its tabs and recent files. */
+function collectWindowState(state: State): WindowState {
+  const { tabs, recentFiles, currentTabId, viewStates } = state;
+  const sessionTabs: SessionTab[] = serializeTabs(tabs, buildContentMap(state), viewStates);
+
+  return {
+    recentFiles,
+    session: {
+      tabs: sessionTabs,
+      currentTabId: currentTabId ?? undefined,
+    },
+  };
+}
+
+/**
+ * Release a `--wait` caller once none of the tabs it opened is pending.
+ * `remaining` is the tab list after the saved or closed tab dropped out.
+ */
+function releaseWaitIfDone(waitId: string | undefined, remaining: TabMeta[]) {
+  if (!waitId || remaining.some((t) => t.waitId === waitId)) return;
+  finishWait(waitId).catch((error) => console.error("Failed to release waiting process:", error));
+}
+
 /**
  * Remove an entry from a record, returning a shallow copy without the key.
  */
@@ -116,6 +203,36 @@ function withoutKey<T>(record: Record<string, T>, key: string): Record<string, T
   return rest;
 }
 
+/**
+ * Tell the user about anything unusual that happened while loading the session.
+ */
+async function reportLoadWarnings(warnings: LoadWarning[]) {
+  for (const warning of warnings) {
+    if (warning.kind === "sessionQuarantined") {
+      const backup = warning.backupPath ? ` A copy was kept at:\n${warning.backupPath}` : "";
+      await message(
+        `The saved session could not be read (${warning.error}). Recovered ${warning.recoveredTabs} tab(s) and ${warning.recoveredRecentFiles} recent file(s).${backup}`,
+        { title: "Session Recovered", kind: "warning" },
+      );
+    } else if (warning.kind === "recoveredFromJournal") {
+      await message(`Unsaved changes in ${warning.tabs} tab(s) were recovered after an unexpected shutdown.`, {
+        title: "Unsaved Changes Recovered",
+        kind: "info",
+      });
+    } else if (warning.kind === "changedOnDisk") {
+      await message(`These files were changed by another program after your unsaved changes to them were made:\n${warning.paths.join("\n")}`, {
+        title: "Files Changed on Disk",
+        kind: "warning",
+      });
+    } else if (warning.kind === "unsavedEditsLost") {
+      await message(`Unsaved changes to these files could not be restored, so they show the saved version:\n${warning.paths.join("\n")}`, {
+        title: "Unsaved Changes Lost",
+        kind: "warning",
+      });
+    }
+  }
+}
+
 /**
  * Derive a FileInfo for an untitled tab based on its filename (for the
  * extension) while keeping sensible defaults for everything else.
@@ -126,6 +243,73 @@ function fileInfoForUntitled(filename: string): FileInfo {
   return { ...DEFAULT_FILE_INFO, extension };
 }
 
+/**
+ * Derive a FileInfo for a tab whose content comes from the cache. A dirty
+ * local tab keeps the version its edits were made on, so saving it still
+ * notices changes made on disk in the meantime.
+ */
+function fileInfoForCached(tab: TabMeta): FileInfo {
+  return { ...fileInfoForUntitled(tab.filename), version: tab.baseVersion };
+}
+
+/**
+ * Turn a saved session into the tab state of the store, loading the active
+ * tab's content from the cache or from disk.
+ */
+async function restoreSession(
+  session: EditorSession,
+): Promise<Pick<State, "tabs" | "currentTabId" | "activeContent" | "contentCache" | "viewStates" | "currentFileInfo">> {
+  const {
+    tabs: loadedTabs,
+    contentCache: initialCache,
+    viewStates,
+  } = session.tabs.length > 0 ? deserializeTabs(session.tabs) : { tabs: [], contentCache: {}, viewStates: {} };
+
+  const currentTabId = (loadedTabs.find((t) => t.id === session.currentTabId) ? session.currentTabId : null) ?? null;
+
+  // Load the active tab's content (from cache or from disk).
+  let activeContent = "";
+  let currentFileInfo: FileInfo | null = null;
+  const contentCache = { ...initialCache };
+
+  if (currentTabId) {
+    const currentTab = loadedTabs.find((t) => t.id === currentTabId);
+
+    if (contentCache[currentTabId] !== undefined) {
+      activeContent = contentCache[currentTabId];
+      delete contentCache[currentTabId]; // promote to activeContent
+
+      // For cached content we derive basic metadata.
+      currentFileInfo = currentTab ? fileInfoForCached(currentTab) : { ...DEFAULT_FILE_INFO };
+    } else if (currentTab?.type === TabType.LOCAL && currentTab.path) {
+      try {
+        const result = await openFile(currentTab.path);
+        if (result) {
+          activeContent = result.content;
+          currentFileInfo = result.fileInfo;
+        }
+      } catch (error) {
+        console.error("Failed to load current tab content:", error);
+      }
+    } else if (currentTab) {
+      // Untitled tab with no cached content.
+      currentFileInfo = fileInfoForUntitled(currentTab.filename);
+    }
+  }
+
+  return { tabs: loadedTabs, currentTabId, activeContent, contentCache, viewStates, currentFileInfo };
+}
+
+/**
+ * Let the `--wait` callers of `tabs` go, for tabs leaving this window
+ * without being closed.
+ */
+function releaseWaits(tabs: TabMeta[]) {
+  for (const waitId of new Set(tabs.flatMap((t) => (t.waitId ? [t.waitId] : [])))) {
+    finishWait(waitId).catch((error) => console.error("Failed to release waiting process:", error));
+  }
+}
+
 // ---------------------------------------------------------------------------
 // Store
 // ---------------------------------------------------------------------------
@@ -135,9 +319,11 @@ const useEditorStore = create<State & { actions: Actions }>((set, get) => ({
   currentTabId: null,
   activeContent: "",
   contentCache: {},
+  viewStates: {},
   currentFileInfo: null,
   recentFiles: {},
   isInitialized: false,
+  cursorRequest: null,
 
   actions: {
     // -----------------------------------------------------------------------
@@ -148,50 +334,13 @@ const useEditorStore = create<State & { actions: Actions }>((set, get) => ({
       try {
         const db = await loadEditorState();
 
-        const { tabs: loadedTabs, contentCache: initialCache } =
-          db.session.tabs.length > 0 ? deserializeTabs(db.session.tabs) : { tabs: [], contentCache: {} };
-
-        const currentTabId = (loadedTabs.find((t) => t.id === db.session.currentTabId) ? db.session.currentTabId : null) ?? null;
-
-        // Load the active tab's content (from cache or from disk).
-        let activeContent = "";
-        let currentFileInfo: FileInfo | null = null;
-        const contentCache = { ...initialCache };
-
-        if (currentTabId) {
-          const currentTab = loadedTabs.find((t) => t.id === currentTabId);
-
-          if (contentCache[currentTabId] !== undefined) {
-            activeContent = contentCache[currentTabId];
-            delete contentCache[currentTabId]; // promote to activeContent
-
-            // For cached content we derive basic metadata.
-            currentFileInfo = currentTab ? fileInfoForUntitled(currentTab.filename) : { ...DEFAULT_FILE_INFO };
-          } else if (currentTab?.type === TabType.LOCAL && currentTab.path) {
-            try {
-              const result = await openFile(currentTab.path);
-              if (result) {
-                activeContent = result.content;
-                currentFileInfo = result.fileInfo;
-              }
-            } catch (error) {
-              console.error("Failed to load current tab content:", error);
-            }
-          } else if (currentTab) {
-            // Untitled tab with no cached content.
-            currentFileInfo = fileInfoForUntitled(currentTab.filename);
-          }
-        }
-
         set({
-          tabs: loadedTabs,
-          currentTabId,
-          activeContent,
-          contentCache,
-          currentFileInfo,
+          ...(await restoreSession(db.session)),
           recentFiles: db.recentFiles,
           isInitialized: true,
         });
+
+        reportLoadWarnings(db.warnings ?? []).catch((error) => console.error("Failed to report load warnings:", error));
       } catch (error) {
         console.error("Failed to initialize editor:", error);
         set({ isInitialized: true });
@@ -199,10 +348,7 @@ const useEditorStore = create<State & { actions: Actions }>((set, get) => ({
 
       // Open files passed via CLI args ("Open with" on cold start).
       try {
-        const cliPaths = await takeCliFilePaths();
-        for (const path of cliPaths) {
-          await get().actions.openByPath(path);
-        }
+        await get().actions.openRequests(await takeCliFilePaths());
       } catch (error) {
         console.error("Failed to open CLI file paths:", error);
       }
@@ -211,23 +357,8 @@ const useEditorStore = create<State & { actions: Actions }>((set, get) => ({
     },
 
     persistSession: async () => {
-      const state = get();
-      const { tabs, recentFiles, currentTabId } = state;
-
-      const contentMap = buildContentMap(state);
-      const sessionTabs: SessionTab[] = serializeTabs(tabs, contentMap);
-
-      const db: DatabaseV2 = {
-        recentFiles,
-        session: {
-          tabs: sessionTabs,
-          currentTabId: currentTabId ?? undefined,
-        },
-        schemaVersion: "V2",
-      };
-
       try {
-        await saveEditorState(db);
+        await saveEditorState(collectWindowState(get()));
       } catch (error) {
         console.error("Failed to persist session:", error);
       }
@@ -259,6 +390,117 @@ const useEditorStore = create<State & { actions: Actions }>((set, get) => ({
       });
     },
 
+    addUntitled: (filename, content, options) => {
+      const id = crypto.randomUUID();
+      const readOnly = options?.readOnly ?? false;
+      const tab: TabMeta = {
+        id,
+        type: TabType.UNTITLED,
+        isDirty: !readOnly && content.length > 0,
+        filename,
+        path: undefined,
+        readOnly,
+      };
+
+      const state = get();
+      const flushedCache = flushActiveToCache(state);
+
+      set({
+        tabs: [...state.tabs, tab],
+        currentTabId: id,
+        activeContent: content,
+        contentCache: flushedCache,
+        currentFileInfo: fileInfoForUntitled(filename),
+      });
+    },
+
+    /**
+     * Open everything a command line asked for. Failures are logged per
+     * request so one bad argument doesn't stop the rest.
+     */
+    openRequests: async (requests) => {
+      const { actions } = get();
+
+      for (const request of requests) {
+        const before = get().currentTabId;
+        try {
+          switch (request.kind) {
+            case "file": {
+              await actions.openByPath(request.path);
+              const tabId = get().currentTabId;
+              if (!tabId) break;
+
+              // A path that doesn't exist yet opens as an unsaved new file.
+              if (request.readOnly || request.newFile) {
+                set((state) => ({
+                  tabs: state.tabs.map((t) =>
+                    t.id === tabId ? { ...t, readOnly: request.readOnly || t.readOnly, isDirty: request.newFile || t.isDirty } : t,
+                  ),
+                }));
+              }
+              if (request.line) {
+                set({ cursorRequest: { tabId, line: request.line, column: request.column ?? 1 } });
+              }
+              break;
+            }
+            case "directory": {
+              const picked = await tauriOpen({ title: "Open File", defaultPath: request.path, multiple: true });
+              for (const path of picked ?? []) {
+                await actions.openByPath(path);
+              }
+              break;
+            }
+            case "diff": {
+              const diff = await diffFiles(request.left, request.right);
+              const name = (path: string) => path.split(/[\\/]/).pop() || path;
+              actions.addUntitled(`${name(request.left)} ↔ ${name(request.right)}.diff`, diff, { readOnly: true });
+              break;
+            }
+            case "stdin":
+              actions.addUntitled(request.filename, request.content, { readOnly: request.readOnly });
+              break;
+            case "tab": {
+              // A clean file already open here just gets focused.
+              const existing = request.tab.isDirty ? undefined : get().tabs.find((t) => t.path && t.path === request.tab.path);
+              if (existing) {
+                await actions.openTab(existing.id);
+                break;
+              }
+
+              const { tabs: moved, contentCache: movedContent, viewStates: movedViews } = deserializeTabs([request.tab]);
+              set((state) => ({
+                tabs: [...state.tabs, ...moved.map((t) => ({ ...t, readOnly: request.readOnly }))],
+                contentCache: { ...state.contentCache, ...movedContent },
+                viewStates: { ...state.viewStates, ...movedViews },
+              }));
+              await actions.openTab(request.tab.id);
+              break;
+            }
+          }
+        } catch (error) {
+          console.error("Failed to open CLI request:", request, error);
+        }
+
+        const opened = get().currentTabId;
+        if (request.waitId && opened && opened !== before) {
+          set((state) => ({ tabs: state.tabs.map((t) => (t.id === opened ? { ...t, waitId: request.waitId } : t)) }));
+        }
+      }
+
+      // Don't keep a `--wait` caller blocked on requests that opened nothing.
+      for (const waitId of new Set(requests.map((r) => r.waitId))) {
+        releaseWaitIfDone(waitId, get().tabs);
+      }
+    },
+
+    clearCursorRequest: () => set({ cursorRequest: null }),
+
+    /** Record where the user is in a tab. Folds the editor doesn't report are kept. */
+    setViewState: (tabId, view) =>
+      set((state) => ({
+        viewStates: { ...state.viewStates, [tabId]: { ...state.viewStates[tabId], ...view } },
+      })),
+
     // -----------------------------------------------------------------------
     // Content editing — the hot path
     // -----------------------------------------------------------------------
@@ -282,10 +524,12 @@ const useEditorStore = create<State & { actions: Actions }>((set, get) => ({
         // Hot path: only update the isolated content string.
         set({ activeContent: content });
       } else {
-        // First edit: flip isDirty (one-time O(n) on the tabs array).
+        // First edit: flip isDirty (one-time O(n) on the tabs array) and
+        // note which version of the file the edits start from.
+        const baseVersion = tab.type === TabType.LOCAL ? get().currentFileInfo?.version : undefined;
         set({
           activeContent: content,
-          tabs: tabs.map((t) => (t.id === currentTabId ? { ...t, isDirty: true } : t)),
+          tabs: tabs.map((t) => (t.id === currentTabId ? { ...t, isDirty: true, baseVersion } : t)),
         });
       }
     },
@@ -317,29 +561,40 @@ const useEditorStore = create<State & { actions: Actions }>((set, get) => ({
         }
 
         if (!pathToSave) return;
-        await saveFile(pathToSave, activeContent);
+        const prevInfo = get().currentFileInfo;
+        const saved = await saveFile(pathToSave, activeContent, {
+          expectedVersion: currentTab.type === TabType.LOCAL ? prevInfo?.version : undefined,
+        });
 
         const filename = pathToSave.split(/[\\/]/).pop() || "Untitled";
         const dotIdx = filename.lastIndexOf(".");
         const extension = dotIdx !== -1 ? filename.slice(dotIdx + 1) : "";
 
         // Recompute file info after save to reflect the new on-disk state.
-        const prevInfo = get().currentFileInfo;
         const updatedFileInfo: FileInfo = {
           lineEnding: prevInfo?.lineEnding ?? "LF",
-          encoding: "UTF-8",
-          fileSize: new Blob([activeContent]).size,
+          encoding: prevInfo?.encoding ?? "UTF-8",
+          hasBom: prevInfo?.hasBom ?? false,
+          fileSize: saved.version.size,
           extension,
+          version: saved.version,
         };
 
+        const savedTabs = tabs.map((t) =>
+          t.id === currentTabId
+            ? { ...t, type: TabType.LOCAL, filename, path: pathToSave, isDirty: false, baseVersion: undefined, waitId: undefined }
+            : t,
+        );
+
         set({
-          tabs: tabs.map((t) => (t.id === currentTabId ? { ...t, type: TabType.LOCAL, filename, path: pathToSave, isDirty: false } : t)),
+          tabs: savedTabs,
           // Content is now on disk and clean — remove from cache if present.
           contentCache: withoutKey(contentCache, currentTabId),
           currentFileInfo: updatedFileInfo,
           recentFiles: {
             ...recentFiles,
             [pathToSave]: {
+              ...recentFiles[pathToSave],
               id: currentTabId,
               filename,
               path: pathToSave,
@@ -347,6 +602,7 @@ const useEditorStore = create<State & { actions: Actions }>((set, get) => ({
             },
           },
         });
+        releaseWaitIfDone(currentTab.waitId, savedTabs);
       } catch (error) {
         console.error("Error saving file:", error);
       }
@@ -385,7 +641,7 @@ const useEditorStore = create<State & { actions: Actions }>((set, get) => ({
         if (flushedCache[existingTab.id] !== undefined) {
           content = flushedCache[existingTab.id];
           // Content came from cache — derive basic metadata.
-          fileInfo = fileInfoForUntitled(existingTab.filename);
+          fileInfo = fileInfoForCached(existingTab);
         } else {
           const result = await openFile(path);
           content = result?.content ?? "";
@@ -402,7 +658,14 @@ const useEditorStore = create<State & { actions: Actions }>((set, get) => ({
       }
 
       try {
-        const result = await openFile(path);
+        // Pick up where the file was left when it was last open.
+        const [result, view] = await Promise.all([
+          openFile(path),
+          getViewState(path).catch((error) => {
+            console.error("Failed to look up view state:", error);
+            return null;
+          }),
+        ]);
         const content = result?.content ?? "";
         const fileInfo = result?.fileInfo ?? { ...DEFAULT_FILE_INFO };
 
@@ -424,10 +687,12 @@ const useEditorStore = create<State & { actions: Actions }>((set, get) => ({
           currentTabId: newId,
           activeContent: content,
           contentCache: flushedCache,
+          viewStates: view ? { ...get().viewStates, [newId]: view } : get().viewStates,
           currentFileInfo: fileInfo,
           recentFiles: {
             ...recentFiles,
             [path]: {
+              ...recentFiles[path],
               id: newId,
               filename,
               path,
@@ -435,6 +700,10 @@ const useEditorStore = create<State & { actions: Actions }>((set, get) => ({
             },
           },
         });
+
+        recordFileOpened(path)
+          .then((file) => set((state) => ({ recentFiles: { ...state.recentFiles, [path]: file } })))
+          .catch((error) => console.error("Failed to record file open:", error));
       } catch (error) {
         console.error("Error opening file:", error);
       }
@@ -462,7 +731,7 @@ const useEditorStore = create<State & { actions: Actions }>((set, get) => ({
       if (flushedCache[id] !== undefined) {
         newContent = flushedCache[id];
         // Content came from in-memory cache — derive basic metadata.
-        newFileInfo = fileInfoForUntitled(tabToOpen.filename);
+        newFileInfo = fileInfoForCached(tabToOpen);
       } else if (tabToOpen.type === TabType.LOCAL && tabToOpen.path) {
         try {
           const result = await openFile(tabToOpen.path);
@@ -490,6 +759,11 @@ const useEditorStore = create<State & { actions: Actions }>((set, get) => ({
     // Tab closing
     // -----------------------------------------------------------------------
 
+    /**
+     * Close a tab, asking to save unsaved changes first. A `moved` tab lives
+     * on in another window, so nothing is asked and its `--wait` caller keeps
+     * waiting.
+     */
     closeTab: async (id: string, options = {}) => {
       const state = get();
       const { tabs, currentTabId, activeContent, contentCache, actions } = state;
@@ -498,7 +772,7 @@ const useEditorStore = create<State & { actions: Actions }>((set, get) => ({
       if (!tabToClose) return true;
 
       // --- Handle dirty-tab confirmation ---
-      if (tabToClose.isDirty && !options.skipConfirmation) {
+      if (tabToClose.isDirty && !options.skipConfirmation && !options.moved) {
         const userChoice = await ask(`"${tabToClose.filename}" has unsaved changes. Do you want to save before closing?`, {
           title: "Unsaved Changes",
           kind: "warning",
@@ -513,7 +787,8 @@ const useEditorStore = create<State & { actions: Actions }>((set, get) => ({
           const tabContent = id === currentTabId ? activeContent : (contentCache[id] ?? "");
 
           if (tabToClose.type === TabType.LOCAL && tabToClose.path) {
-            await saveFile(tabToClose.path, tabContent);
+            const saved = await saveBeforeClose(tabToClose.path, tabContent, tabToClose.baseVersion);
+            if (!saved) return false;
           } else {
             // Untitled tab needs the Save-As flow — must be current for that.
             if (id !== currentTabId) {
@@ -546,7 +821,7 @@ const useEditorStore = create<State & { actions: Actions }>((set, get) => ({
           // Load the new current tab's content.
           if (newCache[newCurrentTabId] !== undefined) {
             newActiveContent = newCache[newCurrentTabId];
-            newFileInfo = fileInfoForUntitled(nextTab.filename);
+            newFileInfo = fileInfoForCached(nextTab);
           } else if (nextTab.type === TabType.LOCAL && nextTab.path) {
             try {
               const result = await openFile(nextTab.path);
@@ -566,13 +841,32 @@ const useEditorStore = create<State & { actions: Actions }>((set, get) => ({
         }
       }
 
+      // Remember where the file was left for when it is opened again.
+      const view = freshState.viewStates[id];
+      if (view && tabToClose.type === TabType.LOCAL && tabToClose.path && !options.moved) {
+        rememberViewState(tabToClose.path, view).catch((error) => console.error("Failed to remember view state:", error));
+      }
+
+      // Keep the tab, as it is after a possible Save As, for reopening. Blank
+      // untitled tabs aren't worth it.
+      const closedTab = freshTabs.find((t) => t.id === id);
+      const closedContent = freshCurrentTabId === id ? freshState.activeContent : freshState.contentCache[id];
+      if (closedTab && !options.moved && (closedTab.type === TabType.LOCAL || closedContent)) {
+        const [sessionTab] = serializeTabs([closedTab], { [id]: closedContent ?? "" }, freshState.viewStates);
+        recordClosedTab(sessionTab).catch((error) => console.error("Failed to record closed tab:", error));
+      }
+
       set({
         tabs: newTabs,
         currentTabId: newCurrentTabId,
         activeContent: newActiveContent,
         contentCache: withoutKey(newCache, newCurrentTabId ?? ""),
+        viewStates: withoutKey(get().viewStates, id),
         currentFileInfo: newFileInfo,
       });
+      if (!options.moved) {
+        releaseWaitIfDone(tabToClose.waitId, newTabs);
+      }
       return true;
     },
 
@@ -582,6 +876,182 @@ const useEditorStore = create<State & { actions: Actions }>((set, get) => ({
       return actions.closeTab(currentTabId);
     },
 
+    /**
+     * Reopen closed tab `id`, or the most recently closed one. A file tab
+     * shows the file as it is on disk; an untitled tab gets its content back.
+     * Resolves to false when there was nothing to reopen.
+     */
+    restoreClosedTab: async (id) => {
+      let closed: SessionTab | null;
+      try {
+        closed = await reopenClosedTab(id);
+      } catch (error) {
+        console.error("Failed to reopen closed tab:", error);
+        return false;
+      }
+      if (!closed) return false;
+
+      if (closed.path) {
+        await get().actions.openByPath(closed.path);
+        return true;
+      }
+
+      // A new id keeps journal entries of the closed tab from applying to it.
+      const { tabs: [tab], contentCache, viewStates } = deserializeTabs([closed]);
+      const newId = crypto.randomUUID();
+      const state = get();
+      const flushedCache = flushActiveToCache(state);
+
+      set({
+        tabs: [...state.tabs, { ...tab, id: newId }],
+        currentTabId: newId,
+        activeContent: contentCache[closed.id] ?? "",
+        contentCache: flushedCache,
+        viewStates: viewStates[closed.id] ? { ...state.viewStates, [newId]: viewStates[closed.id] } : state.viewStates,
+        currentFileInfo: fileInfoForUntitled(tab.filename),
+      });
+      return true;
+    },
+
+    /**
+     * Move a tab, unsaved content included, to the window labelled `target`
+     * or to a new window.
+     */
+    moveTab: async (id, target) => {
+      const state = get();
+      const tab = state.tabs.find((t) => t.id === id);
+      if (!tab) return;
+
+      const [sessionTab] = serializeTabs([tab], buildContentMap(state), state.viewStates);
+      try {
+        await moveTabToWindow(sessionTab, { readOnly: tab.readOnly ?? false, waitId: tab.waitId }, target);
+      } catch (error) {
+        console.error("Failed to move tab:", error);
+        return;
+      }
+
+      await state.actions.closeTab(id, { moved: true });
+    },
+
+    // -----------------------------------------------------------------------
+    // Workspaces
+    // -----------------------------------------------------------------------
+
+    /**
+     * Show workspace `id` in this window. The current tabs are saved and
+     * stay in the workspace being left. Returns false when the switch failed
+     * or another window shows the workspace, which is focused instead.
+     */
+    openWorkspace: async (id) => {
+      let session: EditorSession | null;
+      try {
+        session = await switchWorkspace(id, collectWindowState(get()));
+      } catch (error) {
+        await message(String(error), { title: "Workspace Not Opened", kind: "error" });
+        return false;
+      }
+      if (!session) return false;
+
+      releaseWaits(get().tabs);
+      set({ ...(await restoreSession(session)), cursorRequest: null });
+      return true;
+    },
+
+    /**
+     * Save this window's tabs into its workspace and drop the window from the
+     * session, for a window closing while others stay open.
+     */
+    parkTabs: async () => {
+      releaseWaits(get().tabs);
+
+      try {
+        await detachWindow(collectWindowState(get()));
+      } catch (error) {
+        // Keep the tabs as a window of their own rather than lose them.
+        console.error("Failed to detach window:", error);
+        await get().actions.persistSession();
+      }
+    },
+
+    // -----------------------------------------------------------------------
+    // Changes made by other programs
+    // -----------------------------------------------------------------------
+
+    /**
+     * Another program changed an open file. The active tab is reloaded if
+     * the user agrees and it has no unsaved changes; other clean tabs are
+     * read from disk when next shown anyway. Unsaved changes are never
+     * replaced, only warned about.
+     */
+    fileChangedOnDisk: async (path) => {
+      const tab = get().tabs.find((t) => t.type === TabType.LOCAL && t.path === path);
+      if (!tab) return;
+
+      if (tab.isDirty) {
+        await message(`"${tab.filename}" was changed by another program. Your unsaved changes are kept.`, {
+          title: "File Changed on Disk",
+          kind: "warning",
+        });
+        return;
+      }
+
+      if (get().currentTabId !== tab.id) return;
+
+      const reload = await ask(`"${tab.filename}" was changed by another program. Reload it?`, {
+        title: "File Changed on Disk",
+        kind: "info",
+        okLabel: "Reload",
+        cancelLabel: "Keep",
+      });
+      if (!reload) return;
+
+      try {
+        const result = await openFile(path);
+
+        // The tab may have been edited or left while the user was asked.
+        const current = get().tabs.find((t) => t.id === tab.id);
+        if (!result || !current || current.isDirty || get().currentTabId !== tab.id) return;
+
+        set({ activeContent: result.content, currentFileInfo: result.fileInfo });
+      } catch (error) {
+        console.error("Failed to reload file:", error);
+      }
+    },
+
+    /**
+     * Another program deleted or moved away an open file. The active tab
+     * keeps its text as unsaved changes so it can be saved again.
+     */
+    fileDeletedOnDisk: async (path) => {
+      const tab = get().tabs.find((t) => t.type === TabType.LOCAL && t.path === path);
+      if (!tab) return;
+
+      if (!tab.isDirty && get().currentTabId === tab.id) {
+        set({ tabs: get().tabs.map((t) => (t.id === tab.id ? { ...t, isDirty: true } : t)) });
+      }
+
+      await message(`"${tab.filename}" was deleted or moved by another program. Save the tab to write it back.`, {
+        title: "File Deleted",
+        kind: "warning",
+      });
+    },
+
+    /** Another program renamed an open file; its tab follows it. */
+    fileRenamedOnDisk: (from, to) => {
+      const { tabs, currentTabId, currentFileInfo } = get();
+      const tab = tabs.find((t) => t.type === TabType.LOCAL && t.path === from);
+      if (!tab) return;
+
+      const filename = to.split(/[\\/]/).pop() || to;
+      const dotIdx = filename.lastIndexOf(".");
+      const extension = dotIdx !== -1 ? filename.slice(dotIdx + 1) : "";
+
+      set({
+        tabs: tabs.map((t) => (t.id === tab.id ? { ...t, path: to, filename } : t)),
+        currentFileInfo: currentTabId === tab.id && currentFileInfo ? { ...currentFileInfo, extension } : currentFileInfo,
+      });
+    },
+
     // -----------------------------------------------------------------------
     // Misc
     // -----------------------------------------------------------------------
@@ -599,6 +1069,38 @@ const useEditorStore = create<State & { actions: Actions }>((set, get) => ({
   },
 }));
 
+// Keep the backend file watcher in sync with the set of open local tabs so
+// external modifications are reported while the files are open.
+useEditorStore.subscribe((state, prev) => {
+  if (state.tabs === prev.tabs) return;
+  const paths = state.tabs.flatMap((t) => (t.type === TabType.LOCAL && t.path ? [t.path] : []));
+  watchOpenFiles(paths).catch((error) => console.error("Failed to update file watcher:", error));
+});
+
+// Journal unsaved edits to the backend so they survive a crash. Writes are
+// debounced while typing; closed tabs are recorded immediately.
+const JOURNAL_DEBOUNCE_MS = 1000;
+let journalTimer: ReturnType<typeof setTimeout> | undefined;
+
+useEditorStore.subscribe((state, prev) => {
+  for (const tab of prev.tabs) {
+    if (!state.tabs.some((t) => t.id === tab.id)) {
+      journalTabClosed(tab.id).catch((error) => console.error("Failed to journal closed tab:", error));
+    }
+  }
+
+  if (state.activeContent === prev.activeContent) return;
+
+  clearTimeout(journalTimer);
+  journalTimer = setTimeout(() => {
+    const { currentTabId, tabs, activeContent } = useEditorStore.getState();
+    const tab = tabs.find((t) => t.id === currentTabId);
+    if (!tab || !(tab.isDirty || tab.type === TabType.UNTITLED)) return;
+
+    journalTabContent(tab, activeContent).catch((error) => console.error("Failed to journal tab content:", error));
+  }, JOURNAL_DEBOUNCE_MS);
+});
+
 // ---------------------------------------------------------------------------
 // Hooks — each selector is as narrow as possible so that only the
 // components that truly depend on a given piece of state re-render.
@@ -653,5 +1155,14 @@ export const useRecentFiles = () => useEditorStore((state) => state.recentFiles)
 /** Stable actions reference (never changes between renders). */
 export const useEditorActions = () => useEditorStore((state) => state.actions);
 
+/** Pending cursor move requested from the command line. */
+export const useCursorRequest = () => useEditorStore((state) => state.cursorRequest);
+
+/**
+ * View state last reported for `tabId`. A plain read rather than a hook: the
+ * editor only needs it when a tab is shown, not on every scroll.
+ */
+export const getTabViewState = (tabId: string): ViewState | undefined => useEditorStore.getState().viewStates[tabId];
+
 /** Initialisation flag. */
 export const useIsInitialized = () => useEditorStore((state) => state.isInitialized);
@@ -1,5 +1,7 @@
 import { create } from "zustand";
-import { checkForMigrationToV2, migrateV1ToV2 } from "@/lib/commands";
+import { ask, message } from "@tauri-apps/plugin-dialog";
+import { checkForMigrationToV2, listFailedV1Notes, migrateV1ToV2, retryFailedV1Notes } from "@/lib/commands";
+import { MigrationReport, NoteRef } from "@/lib/types";
 
 type MigrationStatus = "idle" | "checking" | "complete" | "error";
 
@@ -8,10 +10,29 @@ interface MigrationStore {
   error: string | null;
   actions: {
     runMigrations: () => Promise<void>;
+    retryFailed: () => Promise<void>;
     check: () => Promise<void>;
   };
 }
 
+/**
+ * Tell the user about notes that couldn't be migrated and offer to try them
+ * again. Notes that fail stay in the old data, so a retry can be offered on
+ * every start until they make it.
+ */
+async function offerRetry(failed: NoteRef[], retry: () => Promise<void>) {
+  if (failed.length === 0) return;
+
+  const list = failed.map((note) => `• ${note.title}`).join("\n");
+  const again = await ask(`${failed.length} note(s) from the previous version couldn't be moved to your notes folder:\n${list}\n\nTry again now?`, {
+    title: "Notes Not Migrated",
+    kind: "warning",
+    okLabel: "Retry",
+    cancelLabel: "Later",
+  });
+  if (again) await retry();
+}
+
 const useMigrationStore = create<MigrationStore>((set, get) => ({
   status: "idle",
   error: null,
@@ -19,26 +40,55 @@ const useMigrationStore = create<MigrationStore>((set, get) => ({
   actions: {
     runMigrations: async () => {
       try {
-        console.log("migration needed, performing migration");
-        await migrateV1ToV2();
-
+        const report = await migrateV1ToV2();
         set({ status: "complete" });
+
+        if (report.missing.length > 0) {
+          await message(`${report.missing.length} note(s) from the previous version had no file and were left out:\n${report.missing.map((note) => `• ${note.title}`).join("\n")}`, {
+            title: "Notes Missing",
+            kind: "warning",
+          });
+        }
+        await offerRetry(report.failed, get().actions.retryFailed);
       } catch (e) {
         const errorMessage = e instanceof Error ? e.message : "Unknown error occurred";
         set({ status: "error", error: errorMessage });
       }
     },
+    retryFailed: async () => {
+      let report: MigrationReport;
+      try {
+        report = await retryFailedV1Notes();
+      } catch (error) {
+        await message(String(error), { title: "Notes Not Migrated", kind: "error" });
+        return;
+      }
+
+      if (report.failed.length > 0) {
+        const reasons = report.failed.map((note) => `• ${note.title}: ${note.reason}`).join("\n");
+        await message(`These notes still couldn't be moved and will be offered again on next start:\n${reasons}`, {
+          title: "Notes Not Migrated",
+          kind: "warning",
+        });
+      }
+    },
     check: async () => {
-      console.log("Checking migrations...");
       set({ status: "checking" });
 
       // V1 -> V2 migration
       const needsMigration = await checkForMigrationToV2();
-      console.log({ needsMigration });
       if (needsMigration) {
         await get().actions.runMigrations();
-      } else {
-        set({ status: "complete" });
+        return;
+      }
+
+      set({ status: "complete" });
+
+      // Notes an earlier migration couldn't copy.
+      try {
+        await offerRetry(await listFailedV1Notes(), get().actions.retryFailed);
+      } catch (error) {
+        console.error("Failed to list notes not migrated:", error);
       }
     },
   },
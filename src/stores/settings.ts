@@ -8,6 +8,11 @@ enum AppTheme {
 
 const DEFAULT_THEME = AppTheme.DARK;
 const DEFAULT_EDITOR_FONT_SIZE = 16;
+// Same bounds the backend clamps stored sizes to.
+const MIN_EDITOR_FONT_SIZE = 8;
+const MAX_EDITOR_FONT_SIZE = 72;
+
+const clampFontSize = (fontSize: number) => Math.min(MAX_EDITOR_FONT_SIZE, Math.max(MIN_EDITOR_FONT_SIZE, fontSize));
 
 type State = {
   theme: AppTheme;
@@ -21,19 +26,41 @@ type State = {
   };
 };
 
-const useSettingsStore = create<State>()((set) => ({
+type PersistedSettings = { theme: AppTheme; fontSize: number; notesHome?: string };
+
+/**
+ * Persist a partial settings change. Failures are logged but never block the UI.
+ */
+function persistSettings(changes: Partial<PersistedSettings>) {
+  invoke<PersistedSettings>("update_settings", { changes }).catch((error) => {
+    console.error("Failed to persist settings:", error);
+  });
+}
+
+const useSettingsStore = create<State>()((set, get) => ({
   theme: DEFAULT_THEME,
   editorFontSize: DEFAULT_EDITOR_FONT_SIZE,
   actions: {
     loadSettings: async () => {
-      // Load settings from local storage or API
-      const settings = await invoke<{ theme: AppTheme; fontSize: number }>("get_settings");
+      const settings = await invoke<PersistedSettings>("get_settings");
       set({ theme: settings.theme ?? DEFAULT_THEME, editorFontSize: settings.fontSize ?? DEFAULT_EDITOR_FONT_SIZE });
     },
-    setTheme: (theme) => set({ theme }),
-    increaseEditorFontSize: (fontSize) => set((state) => ({ editorFontSize: state.editorFontSize + fontSize })),
-    decreaseEditorFontSize: (fontSize) => set((state) => ({ editorFontSize: state.editorFontSize - fontSize })),
-    resetFontSize: () => set({ editorFontSize: DEFAULT_EDITOR_FONT_SIZE }),
+    setTheme: (theme) => {
+      set({ theme });
+      persistSettings({ theme });
+    },
+    increaseEditorFontSize: (fontSize) => {
+      set((state) => ({ editorFontSize: clampFontSize(state.editorFontSize + fontSize) }));
+      persistSettings({ fontSize: get().editorFontSize });
+    },
+    decreaseEditorFontSize: (fontSize) => {
+      set((state) => ({ editorFontSize: clampFontSize(state.editorFontSize - fontSize) }));
+      persistSettings({ fontSize: get().editorFontSize });
+    },
+    resetFontSize: () => {
+      set({ editorFontSize: DEFAULT_EDITOR_FONT_SIZE });
+      persistSettings({ fontSize: DEFAULT_EDITOR_FONT_SIZE });
+    },
   },
 }));
 